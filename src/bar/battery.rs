@@ -1,73 +1,90 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
+use std::time::Duration;
+
+use crate::config::{ClickAction, ModuleActions, MouseButton};
+use crate::workers::battery_worker::{State, Status};
+
 #[tracker::track]
 pub struct BatteryModel {
-    life: f32,
+    status: Status,
     displaying_percent: bool,
+    #[tracker::do_not_track]
+    actions: ModuleActions,
 }
 
 #[derive(Debug)]
 pub enum Input {
-    Update(f32),
-    ToggleDisplay,
+    Update(Status),
+    Click(MouseButton),
+    UpdateConfig(ModuleActions),
+}
+
+#[derive(Debug)]
+pub enum Output {
+    ShowControlPanel,
+    ArbitrarySwayMsg(String),
 }
 
 #[relm4::component(pub async)]
 impl AsyncComponent for BatteryModel {
-    type Init = ();
+    type Init = ModuleActions;
     type Input = Input;
-    type Output = ();
+    type Output = Output;
     type CommandOutput = ();
 
     view! {
         gtk::Button {
-            #[track = "model.changed_life()"]
-            set_visible: model.life > 0.0,
-            connect_clicked => Input::ToggleDisplay,
+            #[track = "model.changed_status()"]
+            set_visible: !model.status.packs.is_empty(),
+            #[track = "model.changed_status()"]
+            set_tooltip_text: Some(&tooltip(&model.status)),
+            connect_clicked => Input::Click(MouseButton::Left),
             add_css_class: "info_button",
             set_valign: gtk::Align::Center,
+            add_controller = gtk::GestureClick {
+                set_button: gtk::gdk::BUTTON_MIDDLE,
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(Input::Click(MouseButton::Middle))
+                },
+            },
+            add_controller = gtk::GestureClick {
+                set_button: gtk::gdk::BUTTON_SECONDARY,
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(Input::Click(MouseButton::Right))
+                },
+            },
 
             gtk::Box {
                 gtk::Image {
                     #[track = "model.changed(BatteryModel::displaying_percent())"]
                     set_visible: !model.displaying_percent,
 
-                    #[track = "model.changed(BatteryModel::life())"]
-                    set_icon_name: Some(match model.life {
-                        0f32..10f32 => "battery-caution-symbolic",
-                        10f32..20f32 => "battery-low-symbolic",
-                        20f32..30f32 => "battery-level-30-symbolic",
-                        30f32..40f32 => "battery-level-40-symbolic",
-                        40f32..50f32 => "battery-level-50-symbolic",
-                        50f32..60f32 => "battery-level-60-symbolic",
-                        60f32..70f32 => "battery-level-70-symbolic",
-                        70f32..80f32 => "battery-level-80-symbolic",
-                        80f32..90f32 => "battery-level-90-symbolic",
-                        90f32..100f32 => "battery-level-100-symbolic",
-                        _ => "battery-missing-symbolic"
-                    }),
+                    #[track = "model.changed(BatteryModel::status())"]
+                    set_icon_name: Some(&icon(&model.status)),
                 },
 
                 gtk::Label {
                     #[track = "model.changed(BatteryModel::displaying_percent())"]
                     set_visible: model.displaying_percent,
 
-                    #[track = "model.changed(BatteryModel::life())"]
-                    set_label: &format!("{}%", model.life),
+                    #[track = "model.changed(BatteryModel::status())"]
+                    set_label: &format!("{}%", model.status.percent),
                 },
             }
         }
     }
 
     async fn init(
-        _init: Self::Init,
+        actions: Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
         let model = BatteryModel {
-            life: 1.0,
+            status: Status::default(),
             displaying_percent: false,
+            actions,
             tracker: 0,
         };
         let widgets = view_output!();
@@ -78,18 +95,102 @@ impl AsyncComponent for BatteryModel {
     async fn update(
         &mut self,
         msg: Self::Input,
-        _sender: AsyncComponentSender<Self>,
+        sender: AsyncComponentSender<Self>,
         _root: &Self::Root,
     ) {
         self.reset();
 
         match msg {
             Input::Update(x) => {
-                self.set_life(x);
+                self.set_status(x);
+            }
+            Input::Click(button) => {
+                if let Some(x) = self.actions.command(button) {
+                    sender
+                        .output(Output::ArbitrarySwayMsg(format!("exec {x}")))
+                        .unwrap();
+                    return;
+                }
+                match self.actions.action(button) {
+                    // there's nothing to mute
+                    ClickAction::None | ClickAction::ToggleMute => (),
+                    ClickAction::ToggleDisplay => {
+                        self.set_displaying_percent(!self.displaying_percent);
+                    }
+                    ClickAction::ControlPanel => {
+                        sender.output(Output::ShowControlPanel).unwrap();
+                    }
+                }
+            }
+            Input::UpdateConfig(x) => {
+                self.actions = x;
+            }
+        }
+    }
+}
+
+fn icon(status: &Status) -> String {
+    let level = match status.percent {
+        0f32..10f32 => "caution",
+        10f32..20f32 => "low",
+        20f32..30f32 => "level-30",
+        30f32..40f32 => "level-40",
+        40f32..50f32 => "level-50",
+        50f32..60f32 => "level-60",
+        60f32..70f32 => "level-70",
+        70f32..80f32 => "level-80",
+        80f32..90f32 => "level-90",
+        90f32..=100f32 => "level-100",
+        _ => return "battery-missing-symbolic".to_string(),
+    };
+    match status.state {
+        State::Full => "battery-level-100-charged-symbolic".to_string(),
+        State::Charging => format!("battery-{level}-charging-symbolic"),
+        State::Discharging | State::Unknown => format!("battery-{level}-symbolic"),
+    }
+}
+
+fn tooltip(status: &Status) -> String {
+    let mut lines = vec![format!("{}%, {}", status.percent, describe(status.state))];
+    match status.state {
+        State::Charging => {
+            if let Some(x) = status.time_to_full {
+                lines.push(format!("{} until full", format_duration(x)));
             }
-            Input::ToggleDisplay => {
-                self.set_displaying_percent(!self.displaying_percent);
+        }
+        State::Discharging => {
+            if let Some(x) = status.time_to_empty {
+                lines.push(format!("{} left", format_duration(x)));
             }
         }
+        State::Full | State::Unknown => (),
+    }
+    if status.power > 0.0 {
+        lines.push(format!("{:.1} W", status.power));
+    }
+    // the total is enough when there's only one
+    if status.packs.len() > 1 {
+        for x in &status.packs {
+            lines.push(format!("{}: {}%, {}", x.name, x.percent, describe(x.state)));
+        }
+    }
+    lines.join("\n")
+}
+
+fn describe(state: State) -> &'static str {
+    match state {
+        State::Charging => "charging",
+        State::Discharging => "discharging",
+        State::Full => "fully charged",
+        State::Unknown => "not charging",
+    }
+}
+
+/// Formats a duration as hours and minutes, like "2 h 5 min".
+fn format_duration(x: Duration) -> String {
+    let minutes = x.as_secs() / 60;
+    match minutes / 60 {
+        0 => format!("{} min", minutes % 60),
+        hours => format!("{hours} h {} min", minutes % 60),
     }
 }
@@ -1,30 +1,63 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
+use crate::config::{ClickAction, ModuleActions, MouseButton};
+
 #[tracker::track]
 pub struct BrightnessModel {
     brightness: u32,
     displaying_percent: bool,
+    #[tracker::do_not_track]
+    actions: ModuleActions,
 }
 
 #[derive(Debug)]
 pub enum Input {
     Update(u32),
-    ToggleDisplay,
+    Click(MouseButton),
+    Scroll(bool),
+    UpdateConfig(ModuleActions),
+}
+
+#[derive(Debug)]
+pub enum Output {
+    /// Raises the brightness by one step if true, otherwise lowers it.
+    StepBrightness(bool),
+    ShowControlPanel,
+    ArbitrarySwayMsg(String),
 }
 
 #[relm4::component(pub async)]
 impl AsyncComponent for BrightnessModel {
-    type Init = ();
+    type Init = ModuleActions;
     type Input = Input;
-    type Output = ();
+    type Output = Output;
     type CommandOutput = ();
 
     view! {
         gtk::Button {
-            connect_clicked => Input::ToggleDisplay,
+            connect_clicked => Input::Click(MouseButton::Left),
             add_css_class: "info_button",
             set_valign: gtk::Align::Center,
+            add_controller = gtk::GestureClick {
+                set_button: gtk::gdk::BUTTON_MIDDLE,
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(Input::Click(MouseButton::Middle))
+                },
+            },
+            add_controller = gtk::GestureClick {
+                set_button: gtk::gdk::BUTTON_SECONDARY,
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(Input::Click(MouseButton::Right))
+                },
+            },
+            add_controller = gtk::EventControllerScroll {
+                set_flags: gtk::EventControllerScrollFlags::VERTICAL,
+                connect_scroll[sender] => move |_, _, dy| {
+                    sender.input(Input::Scroll(dy < 0.0));
+                    gtk::glib::Propagation::Stop
+                },
+            },
 
             gtk::Box {
                 gtk::Image {
@@ -52,13 +85,14 @@ impl AsyncComponent for BrightnessModel {
     }
 
     async fn init(
-        _init: Self::Init,
+        actions: Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
         let model = BrightnessModel {
             brightness: 0,
             displaying_percent: false,
+            actions,
             tracker: 0,
         };
         let widgets = view_output!();
@@ -69,7 +103,7 @@ impl AsyncComponent for BrightnessModel {
     async fn update(
         &mut self,
         msg: Self::Input,
-        _sender: AsyncComponentSender<Self>,
+        sender: AsyncComponentSender<Self>,
         _root: &Self::Root,
     ) {
         self.reset();
@@ -78,8 +112,31 @@ impl AsyncComponent for BrightnessModel {
             Input::Update(x) => {
                 self.set_brightness(x);
             }
-            Input::ToggleDisplay => {
-                self.set_displaying_percent(!self.displaying_percent);
+            Input::Click(button) => {
+                if let Some(x) = self.actions.command(button) {
+                    sender
+                        .output(Output::ArbitrarySwayMsg(format!("exec {x}")))
+                        .unwrap();
+                    return;
+                }
+                match self.actions.action(button) {
+                    // there's nothing to mute
+                    ClickAction::None | ClickAction::ToggleMute => (),
+                    ClickAction::ToggleDisplay => {
+                        self.set_displaying_percent(!self.displaying_percent);
+                    }
+                    ClickAction::ControlPanel => {
+                        sender.output(Output::ShowControlPanel).unwrap();
+                    }
+                }
+            }
+            Input::Scroll(x) => {
+                if self.actions.scroll {
+                    sender.output(Output::StepBrightness(x)).unwrap();
+                }
+            }
+            Input::UpdateConfig(x) => {
+                self.actions = x;
             }
         }
     }
@@ -0,0 +1,86 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+
+use crate::workers::audio_worker::Volume;
+
+#[tracker::track]
+pub struct MicrophoneModel {
+    volume: Volume,
+    recording: bool,
+}
+
+#[derive(Debug)]
+pub enum Input {
+    Update(Volume),
+    UpdateRecording(bool),
+    ToggleMute,
+}
+
+#[derive(Debug)]
+pub enum Output {
+    ToggleMute,
+}
+
+#[relm4::component(pub async)]
+impl AsyncComponent for MicrophoneModel {
+    type Init = ();
+    type Input = Input;
+    type Output = Output;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Button {
+            // only shown while something is listening
+            #[track = "model.changed_recording()"]
+            set_visible: model.recording,
+            connect_clicked => Input::ToggleMute,
+            add_css_class: "info_button",
+            set_valign: gtk::Align::Center,
+
+            gtk::Image {
+                #[track = "model.changed_volume()"]
+                set_icon_name: Some(if model.volume.muted {
+                    "microphone-sensitivity-muted-symbolic"
+                } else {
+                    "audio-input-microphone-symbolic"
+                }),
+            },
+        }
+    }
+
+    async fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let model = MicrophoneModel {
+            volume: Volume::default(),
+            recording: false,
+            tracker: 0,
+        };
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        sender: AsyncComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        self.reset();
+
+        match msg {
+            Input::Update(x) => {
+                self.set_volume(x);
+            }
+            Input::UpdateRecording(x) => {
+                self.set_recording(x);
+            }
+            Input::ToggleMute => {
+                sender.output(Output::ToggleMute).unwrap();
+            }
+        }
+    }
+}
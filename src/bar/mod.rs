@@ -1,42 +1,78 @@
 mod battery;
 mod brightness;
+mod microphone;
 mod time;
+mod title;
 mod volume;
 mod workspace;
+mod workspace_button;
 
-use swayipc::WindowEvent;
+use swayipc::{WindowEvent, Workspace};
 
-use gtk::{glib::DateTime, prelude::*};
+use gtk::{gdk, glib::DateTime, prelude::*};
 use gtk4_layer_shell::{Edge, Layer, LayerShell};
 use relm4::prelude::*;
 
+use crate::config::{BarConfig, BarLayer, BarModule, BarPosition, Overrides};
+use crate::control_panel::Section;
+use crate::workers::audio_worker::Volume;
+use crate::workers::battery_worker::Status;
+
 pub struct BarModel {
-    workspace: AsyncController<workspace::WorkspaceModel>,
-    brightness: AsyncController<brightness::BrightnessModel>,
-    battery: AsyncController<battery::BatteryModel>,
-    time: AsyncController<time::TimeModel>,
-    volume: AsyncController<volume::VolumeModel>,
+    window: gtk::Window,
+    /// The sway output this bar is shown on, if known.
+    output: Option<String>,
+    modules: Vec<Module>,
 }
 
-#[derive(Debug)]
+/// A running instance of one of the modules listed in the config.
+enum Module {
+    Workspaces(AsyncController<workspace::WorkspaceModel>),
+    Title(AsyncController<title::TitleModel>),
+    Time(AsyncController<time::TimeModel>),
+    Volume(AsyncController<volume::VolumeModel>),
+    Microphone(AsyncController<microphone::MicrophoneModel>),
+    Brightness(AsyncController<brightness::BrightnessModel>),
+    Battery(AsyncController<battery::BatteryModel>),
+    ControlPanel(gtk::Button),
+}
+
+pub struct Init {
+    pub config: BarConfig,
+    pub overrides: Overrides,
+    pub monitor: Option<gdk::Monitor>,
+}
+
+#[derive(Debug, Clone)]
 pub enum Input {
     ToggleControlPanel,
     UpdateBrightness(u32),
-    UpdateBattery(f32),
-    UpdateWorkspaces(i32),
+    UpdateBattery(Status),
+    UpdateWorkspaces(Vec<Workspace>),
     UpdateWindows(Box<WindowEvent>),
+    FocusEmptyWorkspace,
     UpdateTime(DateTime),
-    UpdateVolume(f64),
+    UpdateVolume(Volume),
+    UpdateMicrophone(Volume),
+    UpdateRecording(bool),
+    UpdateConfig(BarConfig, Overrides),
 }
 
 #[derive(Debug)]
 pub enum Output {
     ToggleControlPanel,
+    ArbitrarySwayMsg(String),
+    ToggleMute,
+    StepVolume(bool),
+    StepBrightness(bool),
+    ToggleMicrophoneMute,
+    /// Opens the control panel on a section, or closes it if that section is already shown.
+    ShowControlPanel(Section),
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for BarModel {
-    type Init = ();
+    type Init = Init;
     type Input = Input;
     type Output = Output;
 
@@ -47,73 +83,77 @@ impl SimpleComponent for BarModel {
 
             gtk::CenterBox {
                 add_css_class: "panel",
-                set_vexpand: false,
+                set_orientation: orientation,
 
                 #[wrap(Some)]
+                #[name = "start"]
                 set_start_widget = &gtk::Box {
+                    set_orientation: orientation,
                     set_margin_all: 4,
-                    model.workspace.widget(),
+                    set_spacing: 8,
                 },
 
                 #[wrap(Some)]
+                #[name = "center"]
                 set_center_widget = &gtk::Box {
+                    set_orientation: orientation,
                     set_margin_all: 4,
-                    model.time.widget(),
+                    set_spacing: 4,
                 },
 
                 #[wrap(Some)]
+                #[name = "end"]
                 set_end_widget = &gtk::Box {
+                    set_orientation: orientation,
                     set_margin_all: 4,
                     set_spacing: 4,
-                    model.volume.widget(),
-                    model.brightness.widget(),
-                    model.battery.widget(),
-                    gtk::Button {
-                        set_valign: gtk::Align::Center,
-                        add_css_class: "info_button",
-                        connect_clicked => Input::ToggleControlPanel,
-                        gtk::Image {
-                            set_icon_name: Some("open-menu-symbolic"),
-                        }
-                    }
                 }
             }
         }
     }
 
     fn init(
-        _init: Self::Init,
+        Init {
+            config,
+            overrides,
+            monitor,
+        }: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let workspace = workspace::WorkspaceModel::builder().launch(()).detach();
-        //let workspaces = workspaces::WorkspacesModel::builder().launch(()).detach();
-        let time = time::TimeModel::builder().launch(()).detach();
-        let brightness = brightness::BrightnessModel::builder().launch(()).detach();
-        let battery = battery::BatteryModel::builder().launch(()).detach();
-        let volume = volume::VolumeModel::builder().launch(()).detach();
-
-        let model = BarModel {
-            workspace,
-            brightness,
-            battery,
-            time,
-            volume,
+        let orientation = match config.position.is_vertical() {
+            true => gtk::Orientation::Vertical,
+            false => gtk::Orientation::Horizontal,
+        };
+
+        let mut model = BarModel {
+            window: root.clone(),
+            output: monitor
+                .as_ref()
+                .and_then(|x| x.connector())
+                .map(String::from),
+            modules: Vec::new(),
         };
         let widgets = view_output!();
 
-        widgets.window.init_layer_shell();
-        widgets.window.set_layer(Layer::Top);
-        widgets.window.auto_exclusive_zone_enable();
-        for (anchor, state) in [
-            (Edge::Left, true),
-            (Edge::Right, true),
-            (Edge::Top, true),
-            (Edge::Bottom, false),
+        for (section, modules) in [
+            (&widgets.start, &config.modules.start),
+            (&widgets.center, &config.modules.center),
+            (&widgets.end, &config.modules.end),
         ] {
-            widgets.window.set_anchor(anchor, state);
+            for x in modules {
+                let module = Module::new(*x, &config, &overrides, orientation, &sender);
+                section.append(&module.widget());
+                model.modules.push(module);
+            }
         }
 
+        widgets.window.init_layer_shell();
+        if let Some(x) = &monitor {
+            widgets.window.set_monitor(x);
+        }
+        set_position(&widgets.window, &config);
+
         ComponentParts { model, widgets }
     }
 
@@ -123,19 +163,228 @@ impl SimpleComponent for BarModel {
                 sender.output(Output::ToggleControlPanel).unwrap();
             }
             Input::UpdateBrightness(x) => {
-                self.brightness.sender().emit(brightness::Input::Update(x))
+                for module in &self.modules {
+                    if let Module::Brightness(module) = module {
+                        module.emit(brightness::Input::Update(x));
+                    }
+                }
             }
-            Input::UpdateBattery(x) => self.battery.sender().emit(battery::Input::Update(x)),
-            Input::UpdateWindows(_x) => {}
-            Input::UpdateWorkspaces(i) => {
-                self.workspace.emit(workspace::WorkspaceInput::Select(i));
+            Input::UpdateBattery(x) => {
+                for module in &self.modules {
+                    if let Module::Battery(module) = module {
+                        module.emit(battery::Input::Update(x));
+                    }
+                }
+            }
+            Input::UpdateWindows(x) => {
+                for module in &self.modules {
+                    if let Module::Title(module) = module {
+                        module.emit(title::Input::Update(x.clone()));
+                    }
+                }
+            }
+            Input::FocusEmptyWorkspace => {
+                for module in &self.modules {
+                    if let Module::Title(module) = module {
+                        module.emit(title::Input::Clear);
+                    }
+                }
+            }
+            Input::UpdateWorkspaces(mut x) => {
+                if let Some(output) = &self.output {
+                    x.retain(|x| x.output == *output);
+                }
+                for module in &self.modules {
+                    if let Module::Workspaces(module) = module {
+                        module.emit(workspace::WorkspaceInput::Update(x.clone()));
+                    }
+                }
             }
             Input::UpdateTime(x) => {
-                self.time.emit(time::Input::Update(x));
+                for module in &self.modules {
+                    if let Module::Time(module) = module {
+                        module.emit(time::Input::Update(x.clone()));
+                    }
+                }
             }
             Input::UpdateVolume(x) => {
-                self.volume.emit(volume::Input::Update(x));
+                for module in &self.modules {
+                    if let Module::Volume(module) = module {
+                        module.emit(volume::Input::Update(x));
+                    }
+                }
             }
+            Input::UpdateMicrophone(x) => {
+                for module in &self.modules {
+                    if let Module::Microphone(module) = module {
+                        module.emit(microphone::Input::Update(x));
+                    }
+                }
+            }
+            Input::UpdateRecording(x) => {
+                for module in &self.modules {
+                    if let Module::Microphone(module) = module {
+                        module.emit(microphone::Input::UpdateRecording(x));
+                    }
+                }
+            }
+            // changes to the orientation or modules are handled by recreating the bar
+            Input::UpdateConfig(x, overrides) => {
+                set_position(&self.window, &x);
+                for module in &self.modules {
+                    match module {
+                        Module::Title(module) => module.emit(title::Input::UpdateConfig(
+                            x.window.clone(),
+                            overrides.clone(),
+                        )),
+                        Module::Time(module) => {
+                            module.emit(time::Input::UpdateConfig(x.time.clone()))
+                        }
+                        Module::Volume(module) => {
+                            module.emit(volume::Input::UpdateConfig(x.volume.clone()))
+                        }
+                        Module::Brightness(module) => {
+                            module.emit(brightness::Input::UpdateConfig(x.brightness.clone()))
+                        }
+                        Module::Battery(module) => {
+                            module.emit(battery::Input::UpdateConfig(x.battery.clone()))
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+
+    fn shutdown(&mut self, widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        widgets.window.destroy();
+    }
+}
+
+impl Module {
+    fn new(
+        kind: BarModule,
+        config: &BarConfig,
+        overrides: &Overrides,
+        orientation: gtk::Orientation,
+        sender: &ComponentSender<BarModel>,
+    ) -> Self {
+        match kind {
+            BarModule::Workspaces => Self::Workspaces(
+                workspace::WorkspaceModel::builder()
+                    .launch(orientation)
+                    .forward(sender.output_sender(), |msg| match msg {
+                        workspace::WorkspaceOutput::ArbitrarySwayMsg(x) => {
+                            Output::ArbitrarySwayMsg(x)
+                        }
+                    }),
+            ),
+            BarModule::Title => Self::Title(
+                title::TitleModel::builder()
+                    .launch((config.window.clone(), overrides.clone(), orientation))
+                    .detach(),
+            ),
+            BarModule::Time => Self::Time(
+                time::TimeModel::builder()
+                    .launch(config.time.clone())
+                    .detach(),
+            ),
+            BarModule::Volume => Self::Volume(
+                volume::VolumeModel::builder()
+                    .launch(config.volume.clone())
+                    .forward(sender.output_sender(), |msg| match msg {
+                        volume::Output::ToggleMute => Output::ToggleMute,
+                        volume::Output::StepVolume(x) => Output::StepVolume(x),
+                        volume::Output::ShowControlPanel => {
+                            Output::ShowControlPanel(Section::Volume)
+                        }
+                        volume::Output::ArbitrarySwayMsg(x) => Output::ArbitrarySwayMsg(x),
+                    }),
+            ),
+            BarModule::Microphone => {
+                Self::Microphone(microphone::MicrophoneModel::builder().launch(()).forward(
+                    sender.output_sender(),
+                    |msg| match msg {
+                        microphone::Output::ToggleMute => Output::ToggleMicrophoneMute,
+                    },
+                ))
+            }
+            BarModule::Brightness => Self::Brightness(
+                brightness::BrightnessModel::builder()
+                    .launch(config.brightness.clone())
+                    .forward(sender.output_sender(), |msg| match msg {
+                        brightness::Output::StepBrightness(x) => Output::StepBrightness(x),
+                        brightness::Output::ShowControlPanel => {
+                            Output::ShowControlPanel(Section::Brightness)
+                        }
+                        brightness::Output::ArbitrarySwayMsg(x) => Output::ArbitrarySwayMsg(x),
+                    }),
+            ),
+            // the control panel has no battery section, so it just opens
+            BarModule::Battery => Self::Battery(
+                battery::BatteryModel::builder()
+                    .launch(config.battery.clone())
+                    .forward(sender.output_sender(), |msg| match msg {
+                        battery::Output::ShowControlPanel => Output::ToggleControlPanel,
+                        battery::Output::ArbitrarySwayMsg(x) => Output::ArbitrarySwayMsg(x),
+                    }),
+            ),
+            BarModule::ControlPanel => {
+                let button = gtk::Button::builder()
+                    .valign(gtk::Align::Center)
+                    .css_classes(["info_button"])
+                    .child(&gtk::Image::from_icon_name("open-menu-symbolic"))
+                    .build();
+                let sender = sender.clone();
+                button.connect_clicked(move |_| sender.input(Input::ToggleControlPanel));
+                Self::ControlPanel(button)
+            }
+        }
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        match self {
+            Self::Workspaces(x) => x.widget().clone().upcast(),
+            Self::Title(x) => x.widget().clone().upcast(),
+            Self::Time(x) => x.widget().clone().upcast(),
+            Self::Volume(x) => x.widget().clone().upcast(),
+            Self::Microphone(x) => x.widget().clone().upcast(),
+            Self::Brightness(x) => x.widget().clone().upcast(),
+            Self::Battery(x) => x.widget().clone().upcast(),
+            Self::ControlPanel(x) => x.clone().upcast(),
         }
     }
 }
+
+fn set_position(window: &gtk::Window, config: &BarConfig) {
+    let position = config.position;
+    for (anchor, state) in [
+        (Edge::Left, position != BarPosition::Right),
+        (Edge::Right, position != BarPosition::Left),
+        (Edge::Top, position != BarPosition::Bottom),
+        (Edge::Bottom, position != BarPosition::Top),
+    ] {
+        window.set_anchor(anchor, state);
+    }
+
+    for (edge, margin) in [
+        (Edge::Top, config.margins.top),
+        (Edge::Bottom, config.margins.bottom),
+        (Edge::Left, config.margins.left),
+        (Edge::Right, config.margins.right),
+    ] {
+        window.set_margin(edge, margin);
+    }
+
+    window.set_layer(match config.layer {
+        BarLayer::Background => Layer::Background,
+        BarLayer::Bottom => Layer::Bottom,
+        BarLayer::Top => Layer::Top,
+        BarLayer::Overlay => Layer::Overlay,
+    });
+
+    match config.exclusive_zone {
+        Some(x) => window.set_exclusive_zone(x),
+        None => window.auto_exclusive_zone_enable(),
+    }
+}
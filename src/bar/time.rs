@@ -3,20 +3,24 @@ use gtk::glib::DateTime;
 use gtk::prelude::*;
 use relm4::prelude::*;
 
+use crate::config::TimeConfig;
+
 pub struct TimeModel {
     time: DateTime,
     displaying_date: bool,
+    config: TimeConfig,
 }
 
 #[derive(Debug)]
 pub enum Input {
     Update(DateTime),
+    UpdateConfig(TimeConfig),
     ToggleDisplay,
 }
 
 #[relm4::component(pub async)]
 impl AsyncComponent for TimeModel {
-    type Init = ();
+    type Init = TimeConfig;
     type Input = Input;
     type Output = ();
     type CommandOutput = ();
@@ -30,22 +34,23 @@ impl AsyncComponent for TimeModel {
             gtk::Label {
                 #[watch]
                 set_label: &format!("{}", if model.displaying_date {
-                    model.time.format("%m/%d/%y").unwrap()
+                    model.time.format(&model.config.date_format).unwrap_or_default()
                 } else {
-                    model.time.format("%I:%M %p").unwrap()
+                    model.time.format(&model.config.time_format).unwrap_or_default()
                 }),
             },
         }
     }
 
     async fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
         let model = TimeModel {
             time: DateTime::now_local().unwrap(),
             displaying_date: false,
+            config,
         };
         let widgets = view_output!();
 
@@ -62,6 +67,9 @@ impl AsyncComponent for TimeModel {
             Input::Update(x) => {
                 self.time = x;
             }
+            Input::UpdateConfig(x) => {
+                self.config = x;
+            }
             Input::ToggleDisplay => {
                 self.displaying_date = !self.displaying_date;
             }
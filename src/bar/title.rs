@@ -0,0 +1,144 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use swayipc::{Node, WindowChange, WindowEvent};
+
+use crate::config::{Overrides, WindowConfig};
+
+#[tracker::track]
+pub struct TitleModel {
+    id: Option<i64>,
+    title: String,
+    icon: String,
+    #[tracker::do_not_track]
+    app_name: String,
+    /// The title before it's cut short, so a new width can be applied to it.
+    #[tracker::do_not_track]
+    full_title: String,
+    #[tracker::do_not_track]
+    config: WindowConfig,
+    #[tracker::do_not_track]
+    overrides: Overrides,
+}
+
+#[derive(Debug)]
+pub enum Input {
+    Update(Box<WindowEvent>),
+    Clear,
+    UpdateConfig(WindowConfig, Overrides),
+}
+
+#[relm4::component(pub async)]
+impl AsyncComponent for TitleModel {
+    type Init = (WindowConfig, Overrides, gtk::Orientation);
+    type Input = Input;
+    type Output = ();
+    type CommandOutput = ();
+
+    view! {
+        gtk::Box {
+            #[track = "model.changed_id()"]
+            set_visible: model.id.is_some(),
+            add_css_class: "window_title",
+            set_orientation: orientation,
+            set_valign: gtk::Align::Center,
+            set_halign: gtk::Align::Center,
+            set_spacing: 6,
+
+            gtk::Image {
+                #[track = "model.changed_icon()"]
+                set_icon_name: Some(&model.icon),
+            },
+
+            gtk::Label {
+                #[track = "model.changed_title()"]
+                set_label: &model.title,
+            },
+        }
+    }
+
+    async fn init(
+        (config, overrides, orientation): Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let model = TitleModel {
+            id: None,
+            title: String::new(),
+            icon: String::new(),
+            app_name: String::new(),
+            full_title: String::new(),
+            config,
+            overrides,
+            tracker: 0,
+        };
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        _sender: AsyncComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        self.reset();
+
+        match msg {
+            Input::Update(x) => match x.change {
+                WindowChange::Focus => {
+                    self.show(&x.container);
+                }
+                WindowChange::Title => {
+                    if self.id == Some(x.container.id) || x.container.focused {
+                        self.show(&x.container);
+                    }
+                }
+                WindowChange::Close => {
+                    if self.id == Some(x.container.id) {
+                        self.clear();
+                    }
+                }
+                _ => (),
+            },
+            Input::Clear => {
+                self.clear();
+            }
+            Input::UpdateConfig(config, overrides) => {
+                self.config = config;
+                self.overrides = overrides;
+                if self.id.is_some() {
+                    self.set_icon(self.overrides.apply(self.app_name.clone()));
+                    self.set_title(truncate(&self.full_title, self.config.max_width));
+                }
+            }
+        }
+    }
+}
+
+impl TitleModel {
+    fn show(&mut self, node: &Node) {
+        self.app_name = crate::dock::get_name(node);
+        self.set_id(Some(node.id));
+        self.set_icon(self.overrides.apply(self.app_name.clone()));
+        self.full_title = node.name.clone().unwrap_or_default();
+        self.set_title(truncate(&self.full_title, self.config.max_width));
+    }
+
+    fn clear(&mut self) {
+        self.set_id(None);
+        self.full_title.clear();
+        self.set_title(String::new());
+    }
+}
+
+/// Shortens `title` to at most `width` characters, marking the cut with an ellipsis.
+fn truncate(title: &str, width: usize) -> String {
+    if title.chars().count() <= width {
+        return title.to_owned();
+    }
+
+    let mut x: String = title.chars().take(width.saturating_sub(1)).collect();
+    x.push('…');
+    x
+}
@@ -1,30 +1,65 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
+use crate::config::{ClickAction, ModuleActions, MouseButton};
+use crate::workers::audio_worker::Volume;
+
 #[tracker::track]
 pub struct VolumeModel {
-    volume: f64,
+    volume: Volume,
     displaying_percent: bool,
+    #[tracker::do_not_track]
+    actions: ModuleActions,
 }
 
 #[derive(Debug)]
 pub enum Input {
-    Update(f64),
-    ToggleDisplay,
+    Update(Volume),
+    Click(MouseButton),
+    Scroll(bool),
+    UpdateConfig(ModuleActions),
+}
+
+#[derive(Debug)]
+pub enum Output {
+    ToggleMute,
+    /// Raises the volume by one step if true, otherwise lowers it.
+    StepVolume(bool),
+    ShowControlPanel,
+    ArbitrarySwayMsg(String),
 }
 
 #[relm4::component(pub async)]
 impl AsyncComponent for VolumeModel {
-    type Init = ();
+    type Init = ModuleActions;
     type Input = Input;
-    type Output = ();
+    type Output = Output;
     type CommandOutput = ();
 
     view! {
         gtk::Button {
-            connect_clicked => Input::ToggleDisplay,
+            connect_clicked => Input::Click(MouseButton::Left),
             add_css_class: "info_button",
             set_valign: gtk::Align::Center,
+            add_controller = gtk::GestureClick {
+                set_button: gtk::gdk::BUTTON_MIDDLE,
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(Input::Click(MouseButton::Middle))
+                },
+            },
+            add_controller = gtk::GestureClick {
+                set_button: gtk::gdk::BUTTON_SECONDARY,
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(Input::Click(MouseButton::Right))
+                },
+            },
+            add_controller = gtk::EventControllerScroll {
+                set_flags: gtk::EventControllerScrollFlags::VERTICAL,
+                connect_scroll[sender] => move |_, _, dy| {
+                    sender.input(Input::Scroll(dy < 0.0));
+                    gtk::glib::Propagation::Stop
+                },
+            },
 
             gtk::Box {
                 gtk::Image {
@@ -32,11 +67,12 @@ impl AsyncComponent for VolumeModel {
                     set_visible: !model.displaying_percent,
 
                     #[track = "model.changed_volume()"]
-                    set_icon_name: Some(match model.volume {
-                        0f64 => "audio-volume-muted-symbolic",
-                        1f64..30f64 => "audio-volume-low-symbolic",
-                        30f64..70f64 => "audio-volume-medium-symbolic",
-                        70f64..100f64 => "audio-volume-high-symbolic",
+                    set_icon_name: Some(match model.volume.level {
+                        _ if model.volume.muted => "audio-volume-muted-symbolic",
+                        ..=0f64 => "audio-volume-muted-symbolic",
+                        ..30f64 => "audio-volume-low-symbolic",
+                        ..70f64 => "audio-volume-medium-symbolic",
+                        ..=100f64 => "audio-volume-high-symbolic",
                         _ => "audio-volume-overamplified-symbolic"
                     }),
                 },
@@ -46,20 +82,27 @@ impl AsyncComponent for VolumeModel {
                     set_visible: model.displaying_percent,
 
                     #[track = "model.changed_volume()"]
-                    set_label: &format!("{}%", model.volume.round()),
+                    set_label: &match model.volume.muted {
+                        true => String::from("Muted"),
+                        false => format!("{}%", model.volume.level.round()),
+                    },
                 },
             }
         }
     }
 
     async fn init(
-        _init: Self::Init,
+        actions: Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
         let model = VolumeModel {
-            volume: 1.0,
+            volume: Volume {
+                level: 1.0,
+                muted: false,
+            },
             displaying_percent: false,
+            actions,
             tracker: 0,
         };
         let widgets = view_output!();
@@ -70,7 +113,7 @@ impl AsyncComponent for VolumeModel {
     async fn update(
         &mut self,
         msg: Self::Input,
-        _sender: AsyncComponentSender<Self>,
+        sender: AsyncComponentSender<Self>,
         _root: &Self::Root,
     ) {
         self.reset();
@@ -79,8 +122,33 @@ impl AsyncComponent for VolumeModel {
             Input::Update(x) => {
                 self.set_volume(x);
             }
-            Input::ToggleDisplay => {
-                self.set_displaying_percent(!self.displaying_percent);
+            Input::Click(button) => {
+                if let Some(x) = self.actions.command(button) {
+                    sender
+                        .output(Output::ArbitrarySwayMsg(format!("exec {x}")))
+                        .unwrap();
+                    return;
+                }
+                match self.actions.action(button) {
+                    ClickAction::None => (),
+                    ClickAction::ToggleDisplay => {
+                        self.set_displaying_percent(!self.displaying_percent);
+                    }
+                    ClickAction::ToggleMute => {
+                        sender.output(Output::ToggleMute).unwrap();
+                    }
+                    ClickAction::ControlPanel => {
+                        sender.output(Output::ShowControlPanel).unwrap();
+                    }
+                }
+            }
+            Input::Scroll(x) => {
+                if self.actions.scroll {
+                    sender.output(Output::StepVolume(x)).unwrap();
+                }
+            }
+            Input::UpdateConfig(x) => {
+                self.actions = x;
             }
         }
     }
@@ -1,39 +1,79 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
+use swayipc::Workspace;
+
+use super::workspace_button;
 
 pub struct WorkspaceModel {
-    index: i32,
+    workspaces: AsyncFactoryVecDeque<workspace_button::WorkspaceButtonModel>,
+    /// Names of this output's workspaces, in the order they're shown.
+    names: Vec<String>,
+    /// The workspace this output is showing.
+    visible: Option<usize>,
 }
 
 #[derive(Debug)]
 pub enum WorkspaceInput {
-    Select(i32),
+    Update(Vec<Workspace>),
+    Focus(String),
+    Next,
+    Prev,
+}
+
+#[derive(Debug)]
+pub enum WorkspaceOutput {
+    ArbitrarySwayMsg(String),
 }
 
 #[relm4::component(pub async)]
 impl AsyncComponent for WorkspaceModel {
-    type Init = ();
+    type Init = gtk::Orientation;
     type Input = WorkspaceInput;
-    type Output = ();
+    type Output = WorkspaceOutput;
     type CommandOutput = ();
 
     view! {
         gtk::Box {
             set_valign: gtk::Align::Center,
+            set_halign: gtk::Align::Center,
             add_css_class: "info_button",
-            gtk::Label {
-                #[watch]
-                set_label: &format!("Workspace {}", model.index),
-            }
+            add_controller = gtk::EventControllerScroll {
+                set_flags: gtk::EventControllerScrollFlags::VERTICAL,
+                connect_scroll[sender] => move |_, _, dy| {
+                    sender.input(if dy > 0.0 {
+                        WorkspaceInput::Next
+                    } else {
+                        WorkspaceInput::Prev
+                    });
+                    gtk::glib::Propagation::Stop
+                },
+            },
+
+            #[local_ref]
+            workspaces_box -> gtk::Box {
+                set_orientation: orientation,
+                set_spacing: 4,
+            },
         }
     }
 
     async fn init(
-        _init: Self::Init,
-        _root: Self::Root,
-        _sender: AsyncComponentSender<Self>,
+        orientation: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
-        let model = Self { index: 1 };
+        let workspaces = AsyncFactoryVecDeque::builder()
+            .launch(gtk::Box::default())
+            .forward(sender.input_sender(), |msg| match msg {
+                workspace_button::Output::Focus(x) => WorkspaceInput::Focus(x),
+            });
+
+        let model = Self {
+            workspaces,
+            names: Vec::new(),
+            visible: None,
+        };
+        let workspaces_box = model.workspaces.widget();
         let widgets = view_output!();
         AsyncComponentParts { model, widgets }
     }
@@ -41,13 +81,43 @@ impl AsyncComponent for WorkspaceModel {
     async fn update(
         &mut self,
         msg: Self::Input,
-        _sender: AsyncComponentSender<Self>,
+        sender: AsyncComponentSender<Self>,
         _root: &Self::Root,
     ) {
         match msg {
-            WorkspaceInput::Select(i) => {
-                self.index = i;
+            WorkspaceInput::Update(x) => {
+                self.names = x.iter().map(|x| x.name.clone()).collect();
+                self.visible = x.iter().position(|x| x.visible);
+                let mut workspaces = self.workspaces.guard();
+                workspaces.clear();
+                for workspace in x {
+                    workspaces.push_back(workspace);
+                }
+            }
+            WorkspaceInput::Focus(x) => focus(&sender, &x),
+            // next_on_output would act on the focused output, which may not be this bar's
+            WorkspaceInput::Next | WorkspaceInput::Prev => {
+                let next = matches!(msg, WorkspaceInput::Next);
+                let len = self.names.len();
+                if len == 0 {
+                    return;
+                }
+                let index = match self.visible {
+                    None => 0,
+                    Some(x) if next => (x + 1) % len,
+                    Some(x) => (x + len - 1) % len,
+                };
+                focus(&sender, &self.names[index]);
             }
         }
     }
 }
+
+fn focus(sender: &AsyncComponentSender<WorkspaceModel>, name: &str) {
+    sender
+        .output(WorkspaceOutput::ArbitrarySwayMsg(format!(
+            "workspace \"{}\"",
+            name.replace('\\', "\\\\").replace('"', "\\\"")
+        )))
+        .unwrap();
+}
@@ -0,0 +1,57 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use swayipc::Workspace;
+
+pub struct WorkspaceButtonModel {
+    workspace: Workspace,
+}
+
+#[derive(Debug, Clone)]
+pub enum Input {
+    Clicked,
+}
+
+#[derive(Debug)]
+pub enum Output {
+    Focus(String),
+}
+
+#[relm4::factory(pub async)]
+impl AsyncFactoryComponent for WorkspaceButtonModel {
+    type Init = Workspace;
+    type Input = Input;
+    type Output = Output;
+    type CommandOutput = ();
+    type ParentWidget = gtk::Box;
+
+    view! {
+        #[root]
+        gtk::Button {
+            add_css_class: "workspace",
+            set_class_active: ("focused", self.workspace.focused),
+            set_class_active: ("visible", self.workspace.visible),
+            set_class_active: ("urgent", self.workspace.urgent),
+            set_valign: gtk::Align::Center,
+            set_label: &self.workspace.name,
+            connect_clicked => Input::Clicked,
+        }
+    }
+
+    async fn init_model(
+        workspace: Self::Init,
+        _index: &DynamicIndex,
+        _sender: AsyncFactorySender<Self>,
+    ) -> Self {
+        Self { workspace }
+    }
+
+    async fn update(&mut self, msg: Self::Input, sender: AsyncFactorySender<Self>) {
+        match msg {
+            Input::Clicked => {
+                sender
+                    .output(Output::Focus(self.workspace.name.clone()))
+                    .unwrap();
+            }
+        }
+    }
+}
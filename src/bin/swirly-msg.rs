@@ -0,0 +1,119 @@
+// only part of the protocol is needed on this side
+#[allow(dead_code)]
+#[path = "../ipc.rs"]
+mod ipc;
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    process::ExitCode,
+};
+
+use ipc::{Request, Response};
+
+const USAGE: &str = "Usage: swirly-msg <command> [argument]
+
+Commands:
+    toggle-dock
+    toggle-control-panel
+    toggle-tiling
+    set-tiling <true|false>
+    toggle-notifs
+    set-volume <percent>
+    toggle-mute
+    set-mute <true|false>
+    set-brightness <percent> [fade milliseconds]
+    reload-css
+    query
+    raw <json>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let request = match parse(&args) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match send(&request) {
+        Ok(Response::Error { message }) => {
+            eprintln!("swirly: {message}");
+            ExitCode::FAILURE
+        }
+        Ok(Response::Ok) => ExitCode::SUCCESS,
+        Ok(response) => {
+            println!("{}", serde_json::to_string_pretty(&response).unwrap());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Failed to talk to swirly: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse(args: &[String]) -> Result<Request, String> {
+    let argument = || {
+        args.get(1)
+            .ok_or_else(|| format!("{} needs an argument.", args[0]))
+    };
+
+    match args.first().map(String::as_str) {
+        Some("toggle-dock") => Ok(Request::ToggleDock),
+        Some("toggle-control-panel") => Ok(Request::ToggleControlPanel),
+        Some("toggle-tiling") => Ok(Request::ToggleTiling),
+        Some("set-tiling") => Ok(Request::SetTiling {
+            tiling: argument()?
+                .parse()
+                .map_err(|e| format!("Invalid tiling state: {e}"))?,
+        }),
+        Some("toggle-notifs") => Ok(Request::ToggleNotifs),
+        Some("set-volume") => Ok(Request::SetVolume {
+            volume: argument()?
+                .parse()
+                .map_err(|e| format!("Invalid volume: {e}"))?,
+        }),
+        Some("toggle-mute") => Ok(Request::ToggleMute),
+        Some("set-mute") => Ok(Request::SetMute {
+            muted: argument()?
+                .parse()
+                .map_err(|e| format!("Invalid mute state: {e}"))?,
+        }),
+        Some("set-brightness") => Ok(Request::SetBrightness {
+            brightness: argument()?
+                .parse()
+                .map_err(|e| format!("Invalid brightness: {e}"))?,
+            fade: args
+                .get(2)
+                .map(|x| x.parse())
+                .transpose()
+                .map_err(|e| format!("Invalid fade: {e}"))?,
+        }),
+        Some("reload-css") => Ok(Request::ReloadCss),
+        Some("query") => Ok(Request::Query),
+        Some("raw") => {
+            serde_json::from_str(argument()?).map_err(|e| format!("Invalid request: {e}"))
+        }
+        Some(x) => Err(format!("Unknown command {x}.")),
+        None => Err(String::from("No command given.")),
+    }
+}
+
+fn send(request: &Request) -> Result<Response, String> {
+    let path = ipc::socket_path().ok_or("XDG_RUNTIME_DIR is not set")?;
+    let mut stream = UnixStream::connect(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+
+    let mut buf = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    buf.push('\n');
+    stream
+        .write_all(buf.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&line).map_err(|e| format!("Invalid response: {e}"))
+}
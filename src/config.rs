@@ -0,0 +1,617 @@
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de, Deserialize, Deserializer};
+
+pub const FILE_NAME: &str = "swirly.toml";
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bar: BarConfig,
+    pub dock: DockConfig,
+    pub control_panel: ControlPanelConfig,
+    pub osd: OsdConfig,
+    pub workers: WorkersConfig,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct BarConfig {
+    pub position: BarPosition,
+    pub layer: BarLayer,
+    pub margins: Margins,
+    /// Space other windows keep clear of the bar, or its own size if unset.
+    pub exclusive_zone: Option<i32>,
+    pub modules: BarModules,
+    pub time: TimeConfig,
+    pub window: WindowConfig,
+    pub volume: ModuleActions,
+    pub brightness: ModuleActions,
+    pub battery: ModuleActions,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BarPosition {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl BarPosition {
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Self::Left | Self::Right)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BarLayer {
+    Background,
+    Bottom,
+    #[default]
+    Top,
+    Overlay,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Margins {
+    pub top: i32,
+    pub bottom: i32,
+    pub left: i32,
+    pub right: i32,
+}
+
+/// The modules shown in each section of the bar, in order.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BarModules {
+    pub start: Vec<BarModule>,
+    pub center: Vec<BarModule>,
+    pub end: Vec<BarModule>,
+}
+
+impl Default for BarModules {
+    fn default() -> Self {
+        Self {
+            start: vec![BarModule::Workspaces, BarModule::Title],
+            center: vec![BarModule::Time],
+            end: vec![
+                BarModule::Microphone,
+                BarModule::Volume,
+                BarModule::Brightness,
+                BarModule::Battery,
+                BarModule::ControlPanel,
+            ],
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BarModule {
+    Workspaces,
+    Title,
+    Time,
+    Volume,
+    /// Only visible while an application is recording.
+    Microphone,
+    Brightness,
+    Battery,
+    ControlPanel,
+}
+
+/// What clicking or scrolling on the volume, brightness or battery module does.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModuleActions {
+    /// Scrolling steps the volume or brightness up and down.
+    pub scroll: bool,
+    pub left_click: ClickAction,
+    pub middle_click: ClickAction,
+    pub right_click: ClickAction,
+    pub commands: ButtonCommands,
+}
+
+impl Default for ModuleActions {
+    fn default() -> Self {
+        Self {
+            scroll: true,
+            left_click: ClickAction::ToggleDisplay,
+            middle_click: ClickAction::ToggleMute,
+            right_click: ClickAction::ControlPanel,
+            commands: ButtonCommands::default(),
+        }
+    }
+}
+
+impl ModuleActions {
+    pub fn action(&self, button: MouseButton) -> ClickAction {
+        match button {
+            MouseButton::Left => self.left_click,
+            MouseButton::Middle => self.middle_click,
+            MouseButton::Right => self.right_click,
+        }
+    }
+
+    pub fn command(&self, button: MouseButton) -> Option<&str> {
+        match button {
+            MouseButton::Left => self.commands.left.as_deref(),
+            MouseButton::Middle => self.commands.middle.as_deref(),
+            MouseButton::Right => self.commands.right.as_deref(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickAction {
+    None,
+    /// Switches between the icon and the percentage.
+    ToggleDisplay,
+    /// Only does anything on the volume module.
+    ToggleMute,
+    /// Opens the control panel on the module's section.
+    ControlPanel,
+}
+
+/// Shell commands run through sway instead of a button's action.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ButtonCommands {
+    pub left: Option<String>,
+    pub middle: Option<String>,
+    pub right: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeConfig {
+    /// `strftime`-style format used when showing the time.
+    pub time_format: String,
+    /// `strftime`-style format used when showing the date.
+    pub date_format: String,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            time_format: String::from("%I:%M %p"),
+            date_format: String::from("%m/%d/%y"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    /// Titles longer than this many characters are cut short.
+    pub max_width: usize,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self { max_width: 50 }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DockConfig {
+    pub overrides: Overrides,
+    pub launchables: Vec<Launchable>,
+    /// Shows a dock on every output instead of only the first one.
+    pub all_outputs: bool,
+}
+
+/// Maps the app id or class of a window onto the icon shown for it.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Overrides(HashMap<String, String>);
+
+impl Overrides {
+    pub fn apply(&self, name: String) -> String {
+        match self.0.get(&name) {
+            Some(x) => x.to_owned(),
+            None => name,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Launchable {
+    pub icon: String,
+    pub command: String,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControlPanelConfig {
+    pub position: Corner,
+}
+
+/// The popup shown when the volume or brightness changes.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct OsdConfig {
+    pub enabled: bool,
+    /// Milliseconds the popup stays up after the last change before fading out.
+    pub timeout: u64,
+}
+
+impl Default for OsdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout: 1500,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkersConfig {
+    pub audio: AudioConfig,
+    pub battery: BatteryConfig,
+    pub brightness: BrightnessConfig,
+    pub time: TimeWorkerConfig,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    pub backend: AudioBackend,
+    /// Moves playing streams over when a different output device is chosen.
+    pub move_streams: bool,
+    /// Highest volume in percent that can be set, which boosts the sound above 100.
+    #[serde(deserialize_with = "percent")]
+    pub max_volume: f64,
+    /// Percent the volume changes by when scrolling or using the keyboard.
+    #[serde(deserialize_with = "percent")]
+    pub step: f64,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            backend: AudioBackend::default(),
+            move_streams: true,
+            max_volume: 100.0,
+            step: 5.0,
+        }
+    }
+}
+
+/// Which sound server to talk to.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioBackend {
+    /// PipeWire if it's running, otherwise PulseAudio.
+    #[default]
+    Auto,
+    #[serde(rename = "pipewire")]
+    PipeWire,
+    #[serde(rename = "pulseaudio")]
+    PulseAudio,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryConfig {
+    /// Seconds between battery refreshes.
+    #[serde(deserialize_with = "interval")]
+    pub interval: u64,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self { interval: 5 }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrightnessConfig {
+    /// Seconds between brightness checks, only made when uevents can't be listened for.
+    #[serde(deserialize_with = "interval")]
+    pub interval: u64,
+    /// Ways of setting brightness, tried in order until one works for a display.
+    pub backends: Vec<BrightnessBackendKind>,
+    /// Percent the brightness changes by when scrolling over the bar's brightness module.
+    #[serde(deserialize_with = "step")]
+    pub step: u32,
+    /// Milliseconds changes take to fade in, or zero to change at once.
+    pub fade: u64,
+    /// Where backlights are listed, which can point at a fake sysfs for testing.
+    pub path: PathBuf,
+    /// Also controls external monitors over DDC/CI.
+    pub ddc: bool,
+    /// Where display connectors are listed, to name displays after sway outputs.
+    pub drm_path: PathBuf,
+    /// Where the i2c buses monitors are reached through are.
+    pub i2c_path: PathBuf,
+}
+
+impl Default for BrightnessConfig {
+    fn default() -> Self {
+        Self {
+            interval: 5,
+            backends: vec![
+                BrightnessBackendKind::Sysfs,
+                BrightnessBackendKind::Logind,
+                BrightnessBackendKind::Brightnessctl,
+                BrightnessBackendKind::Ddc,
+            ],
+            step: 5,
+            fade: 0,
+            path: PathBuf::from("/sys/class/backlight"),
+            ddc: true,
+            drm_path: PathBuf::from("/sys/class/drm"),
+            i2c_path: PathBuf::from("/dev"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeWorkerConfig {
+    /// Seconds between clock updates.
+    #[serde(deserialize_with = "interval")]
+    pub interval: u64,
+}
+
+impl Default for TimeWorkerConfig {
+    fn default() -> Self {
+        Self { interval: 2 }
+    }
+}
+
+/// Reads an interval in seconds, which can't be zero or the worker would never sleep.
+fn interval<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match u64::deserialize(deserializer)? {
+        0 => Err(de::Error::invalid_value(
+            de::Unexpected::Unsigned(0),
+            &"a number of seconds above zero",
+        )),
+        x => Ok(x),
+    }
+}
+
+/// Reads a volume percentage, which has to be above zero
+/// or the sliders would have no range and scrolling would do nothing.
+fn percent<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    match f64::deserialize(deserializer)? {
+        x if x > 0.0 => Ok(x),
+        x => Err(de::Error::invalid_value(
+            de::Unexpected::Float(x),
+            &"a percentage above zero",
+        )),
+    }
+}
+
+/// Reads a brightness step, which can't be zero or scrolling would do nothing.
+fn step<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match u32::deserialize(deserializer)? {
+        0 => Err(de::Error::invalid_value(
+            de::Unexpected::Unsigned(0),
+            &"a percentage above zero",
+        )),
+        x => Ok(x),
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BrightnessBackendKind {
+    /// Writing to the backlight directly.
+    Sysfs,
+    /// Asking logind over D-Bus, which doesn't need any special permissions.
+    Logind,
+    Brightnessctl,
+    /// DDC/CI, which only works for external monitors.
+    Ddc,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        key: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            Error::Parse {
+                path,
+                line,
+                column,
+                key,
+                message,
+            } => {
+                write!(f, "{}:{line}:{column}: ", path.display())?;
+                if !key.is_empty() && key != "." {
+                    write!(f, "{key}: ")?;
+                }
+                write!(f, "{message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Returns `XDG_CONFIG_HOME/swirly/` or `HOME/.config/swirly/`.
+pub fn dir() -> Option<PathBuf> {
+    if let Some(x) = std::env::var_os("XDG_CONFIG_HOME") {
+        Some(PathBuf::from(x).join("swirly"))
+    } else {
+        std::env::var_os("HOME").map(|x| PathBuf::from(x).join(".config/swirly"))
+    }
+}
+
+/// Loads the config from its default location,
+/// logging any error and falling back to the defaults.
+pub fn load() -> Config {
+    let path = match dir() {
+        Some(x) => x.join(FILE_NAME),
+        None => {
+            log::error!("Failed to find config directory.");
+            return Config::default();
+        }
+    };
+
+    if !path.exists() {
+        log::info!("No config found at {}, using defaults.", path.display());
+        return Config::default();
+    }
+
+    match load_from(&path) {
+        Ok(x) => x,
+        Err(e) => {
+            log::error!("Failed to load config: {e}");
+            Config::default()
+        }
+    }
+}
+
+pub fn load_from(path: &Path) -> Result<Config, Error> {
+    let buf = fs::read_to_string(path).map_err(|e| Error::Io(path.to_owned(), e))?;
+    parse(path, &buf)
+}
+
+pub fn parse(path: &Path, buf: &str) -> Result<Config, Error> {
+    serde_path_to_error::deserialize(toml::Deserializer::new(buf)).map_err(|e| {
+        let key = e.path().to_string();
+        let inner = e.into_inner();
+        let (line, column) = match inner.span() {
+            Some(span) => position(buf, span.start),
+            None => (1, 1),
+        };
+
+        Error::Parse {
+            path: path.to_owned(),
+            line,
+            column,
+            key,
+            message: inner.message().to_owned(),
+        }
+    })
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn position(buf: &str, offset: usize) -> (usize, usize) {
+    let before = &buf[..offset.min(buf.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |x| x + 1) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(buf: &str) -> String {
+        parse(Path::new("swirly.toml"), buf)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn reads_an_empty_file_as_the_defaults() {
+        let config = parse(Path::new("swirly.toml"), "").unwrap();
+        assert_eq!(config.workers.audio, AudioConfig::default());
+        assert_eq!(config.workers.time.interval, 2);
+    }
+
+    #[test]
+    fn reads_values() {
+        let config = parse(
+            Path::new("swirly.toml"),
+            "[workers.audio]\nmax_volume = 150\nstep = 2.5\n\n[workers.brightness]\nstep = 10\n",
+        )
+        .unwrap();
+        assert_eq!(config.workers.audio.max_volume, 150.0);
+        assert_eq!(config.workers.audio.step, 2.5);
+        assert_eq!(config.workers.brightness.step, 10);
+    }
+
+    #[test]
+    fn reports_where_unknown_keys_are() {
+        let e = error("[bar]\nposition = \"top\"\ncolour = \"red\"\n");
+        assert!(e.starts_with("swirly.toml:3:1: bar.colour: "), "{e}");
+        assert!(e.contains("unknown field `colour`"), "{e}");
+    }
+
+    #[test]
+    fn reports_where_values_of_the_wrong_type_are() {
+        let e = error("[workers.time]\ninterval = \"soon\"\n");
+        assert!(
+            e.starts_with("swirly.toml:2:12: workers.time.interval: "),
+            "{e}"
+        );
+    }
+
+    #[test]
+    fn rejects_zero_intervals() {
+        let e = error("[workers.battery]\ninterval = 0\n");
+        assert_eq!(
+            e,
+            "swirly.toml:2:12: workers.battery.interval: \
+            invalid value: integer `0`, expected a number of seconds above zero"
+        );
+    }
+
+    #[test]
+    fn rejects_steps_and_volumes_of_zero_or_below() {
+        let e = error("[workers.audio]\nstep = 0\n");
+        assert!(
+            e.starts_with("swirly.toml:2:8: workers.audio.step: "),
+            "{e}"
+        );
+        assert!(e.contains("expected a percentage above zero"), "{e}");
+
+        let e = error("[workers.audio]\nmax_volume = -50\n");
+        assert!(
+            e.starts_with("swirly.toml:2:14: workers.audio.max_volume: "),
+            "{e}"
+        );
+
+        let e = error("[workers.brightness]\nstep = 0\n");
+        assert!(
+            e.starts_with("swirly.toml:2:8: workers.brightness.step: "),
+            "{e}"
+        );
+    }
+}
@@ -1,84 +1,78 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
-#[tracker::track]
+use crate::workers::brightness_worker::Display;
+
 pub struct BrightnessModel {
-    brightness: u32,
+    pub display: Display,
 }
 
 #[derive(Debug)]
 pub enum Input {
-    Update(u32),
+    Update(Display),
     Changed(u32),
 }
 
 #[derive(Debug)]
 pub enum Output {
-    SetBrightness(u32),
+    /// The display's id and its new brightness.
+    SetBrightness(String, u32),
 }
 
-#[relm4::component(pub async)]
-impl AsyncComponent for BrightnessModel {
-    type Init = ();
+#[relm4::factory(pub async)]
+impl AsyncFactoryComponent for BrightnessModel {
+    type Init = Display;
     type Input = Input;
     type Output = Output;
     type CommandOutput = ();
+    type ParentWidget = gtk::Box;
 
     view! {
+        #[root]
         gtk::Box {
-            add_css_class: "container",
-            set_halign: gtk::Align::Fill,
             set_spacing: 4,
             set_orientation: gtk::Orientation::Vertical,
 
             gtk::Label {
-                set_halign: gtk::Align::Center,
-                set_text: "Brightness"
+                set_halign: gtk::Align::Start,
+                set_ellipsize: gtk::pango::EllipsizeMode::End,
+                #[watch]
+                set_text: &self.display.name,
             },
 
             gtk::Scale {
                 set_halign: gtk::Align::Fill,
                 set_range: (0.0, 100.0),
 
-                #[track = "model.changed(BrightnessModel::brightness())"]
-                set_value: model.brightness as f64,
+                // only the user moving the slider should change the brightness
+                #[watch]
+                #[block_signal(value_handler)]
+                set_value: self.display.brightness as f64,
 
                 connect_value_changed[sender] => move |x| {
                     sender.input(Input::Changed(x.value() as u32))
-                },
+                } @value_handler,
             }
         }
     }
 
-    async fn init(
-        _init: Self::Init,
-        root: Self::Root,
-        sender: AsyncComponentSender<Self>,
-    ) -> AsyncComponentParts<Self> {
-        let model = BrightnessModel {
-            brightness: 0,
-            tracker: 0,
-        };
-
-        let widgets = view_output!();
-
-        AsyncComponentParts { model, widgets }
+    async fn init_model(
+        display: Self::Init,
+        _index: &DynamicIndex,
+        _sender: AsyncFactorySender<Self>,
+    ) -> Self {
+        Self { display }
     }
 
-    async fn update(
-        &mut self,
-        msg: Self::Input,
-        sender: AsyncComponentSender<Self>,
-        _root: &Self::Root,
-    ) {
-        self.reset();
-
+    async fn update(&mut self, msg: Self::Input, sender: AsyncFactorySender<Self>) {
         match msg {
             Input::Update(x) => {
-                self.set_brightness(x);
+                self.display = x;
             }
             Input::Changed(x) => {
-                sender.output(Output::SetBrightness(x)).unwrap();
+                sender
+                    .output(Output::SetBrightness(self.display.id.clone(), x))
+                    .unwrap();
             }
         }
     }
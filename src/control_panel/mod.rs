@@ -1,48 +1,102 @@
 mod brightness;
+mod stream;
 mod volume;
 
-use std::process::Command;
-
 use gtk::prelude::*;
 use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::factory::AsyncFactoryVecDeque;
 use relm4::prelude::*;
 
+use crate::config::{AudioConfig, ControlPanelConfig, Corner, Overrides};
+use crate::workers::audio_worker::{Device, Kind, Stream, Volume};
+use crate::workers::brightness_worker::Display;
+
 #[tracker::track]
 pub struct ControlPanelModel {
+    #[tracker::do_not_track]
+    window: gtk::Window,
     visible: bool,
+    /// The section last opened from the bar, which opening it again closes the panel.
+    #[tracker::do_not_track]
+    section: Option<Section>,
     dock_enabled: bool,
     tiling: bool,
     notifs: bool,
     #[tracker::do_not_track]
-    brightness: AsyncController<brightness::BrightnessModel>,
+    displays: AsyncFactoryVecDeque<brightness::BrightnessModel>,
+    displays_count: usize,
     #[tracker::do_not_track]
     volume: AsyncController<volume::VolumeModel>,
+    #[tracker::do_not_track]
+    microphone: AsyncController<volume::VolumeModel>,
+    #[tracker::do_not_track]
+    streams: AsyncFactoryVecDeque<stream::StreamModel>,
+    streams_count: usize,
+    #[tracker::do_not_track]
+    mixer: gtk::Expander,
+    #[tracker::do_not_track]
+    overrides: Overrides,
+    #[tracker::do_not_track]
+    audio: AudioConfig,
+}
+
+/// A part of the control panel the bar can open it on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Volume,
+    Brightness,
 }
 
 #[derive(Debug)]
 pub enum Input {
     Toggle,
+    ToggleSection(Section),
     ToggleDock,
-    UpdateBrightness(u32),
-    SetBrightness(u32),
+    UpdateDisplays(Vec<Display>),
+    SetBrightness(String, u32),
     ReloadCSS,
     ToggleTiling,
-    UpdateVolume(f64),
+    UpdateVolume(Volume),
     SetVolume(f64),
+    ToggleMute,
+    UpdateSinks(Vec<Device>, String),
+    SetDefaultSink(String),
+    UpdateMicrophone(Volume),
+    SetMicrophoneVolume(f64),
+    ToggleMicrophoneMute,
+    UpdateSources(Vec<Device>, String),
+    SetDefaultSource(String),
+    UpdateStreams(Vec<Stream>),
+    SetStreamVolume(u32, f64),
+    SetStreamMute(u32, bool),
     ToggleNotifs,
+    UpdateDock(bool),
+    UpdateTiling(bool),
+    UpdateNotifs(bool),
+    UpdateConfig(ControlPanelConfig, Overrides, AudioConfig),
 }
 
 #[derive(Debug)]
 pub enum Output {
-    SetBrightness(u32),
+    /// A display's id and its new brightness.
+    SetBrightness(String, u32),
     SetVolume(f64),
+    ToggleMute,
+    SetDefaultSink(String),
+    SetMicrophoneVolume(f64),
+    ToggleMicrophoneMute,
+    SetDefaultSource(String),
+    SetStreamVolume(u32, f64),
+    SetStreamMute(u32, bool),
     ToggleDock,
     ToggleTiling(bool),
+    ToggleNotifs,
+    ReloadCSS,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for ControlPanelModel {
-    type Init = ();
+    type Init = (ControlPanelConfig, Overrides, AudioConfig);
     type Input = Input;
     type Output = Output;
 
@@ -88,52 +142,103 @@ impl SimpleComponent for ControlPanelModel {
                         connect_clicked => Input::ToggleNotifs,
                     },
                 },
-                model.brightness.widget(),
+                gtk::Box {
+                    add_css_class: "container",
+                    set_spacing: 4,
+                    set_orientation: gtk::Orientation::Vertical,
+                    #[track = "model.changed_displays_count()"]
+                    set_visible: model.displays_count > 0,
+
+                    gtk::Label {
+                        set_halign: gtk::Align::Center,
+                        set_text: "Brightness",
+                    },
+
+                    #[local_ref]
+                    displays_box -> gtk::Box {
+                        set_spacing: 8,
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+                },
                 model.volume.widget(),
+                model.microphone.widget(),
+
+                #[local_ref]
+                mixer -> gtk::Expander {
+                    add_css_class: "container",
+                    set_label: Some("Applications"),
+                    #[track = "model.changed_streams_count()"]
+                    set_visible: model.streams_count > 0,
+
+                    #[local_ref]
+                    streams_box -> gtk::Box {
+                        set_spacing: 8,
+                        set_margin_top: 4,
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+                },
             }
         }
     }
 
     fn init(
-        _init: Self::Init,
+        (config, overrides, audio): Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let brightness = brightness::BrightnessModel::builder().launch(()).forward(
-            sender.input_sender(),
-            |msg| match msg {
-                brightness::Output::SetBrightness(x) => Input::SetBrightness(x),
-            },
-        );
-        let volume = volume::VolumeModel::builder().launch(()).forward(
-            sender.input_sender(),
-            |msg| match msg {
+        let displays = AsyncFactoryVecDeque::builder()
+            .launch(gtk::Box::default())
+            .forward(sender.input_sender(), |msg| match msg {
+                brightness::Output::SetBrightness(id, x) => Input::SetBrightness(id, x),
+            });
+        let volume = volume::VolumeModel::builder()
+            .launch((Kind::Output, audio.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
                 volume::Output::SetVolume(x) => Input::SetVolume(x),
-            },
-        );
+                volume::Output::ToggleMute => Input::ToggleMute,
+                volume::Output::SetDefault(x) => Input::SetDefaultSink(x),
+            });
+        let microphone = volume::VolumeModel::builder()
+            .launch((Kind::Input, audio.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                volume::Output::SetVolume(x) => Input::SetMicrophoneVolume(x),
+                volume::Output::ToggleMute => Input::ToggleMicrophoneMute,
+                volume::Output::SetDefault(x) => Input::SetDefaultSource(x),
+            });
+        let streams = AsyncFactoryVecDeque::builder()
+            .launch(gtk::Box::default())
+            .forward(sender.input_sender(), |msg| match msg {
+                stream::Output::SetVolume(index, x) => Input::SetStreamVolume(index, x),
+                stream::Output::SetMute(index, x) => Input::SetStreamMute(index, x),
+            });
 
         let model = Self {
+            window: root.clone(),
             visible: false,
+            section: None,
             dock_enabled: true,
             tiling: true,
             notifs: true,
-            brightness,
+            displays,
+            displays_count: 0,
             volume,
+            microphone,
+            streams,
+            streams_count: 0,
+            mixer: gtk::Expander::default(),
+            overrides,
+            audio,
             tracker: 0,
         };
 
+        let displays_box = model.displays.widget();
+        let streams_box = model.streams.widget();
+        let mixer = &model.mixer;
         let widgets = view_output!();
 
         widgets.window.init_layer_shell();
         widgets.window.set_layer(Layer::Top);
-        for (anchor, state) in [
-            (Edge::Left, false),
-            (Edge::Right, true),
-            (Edge::Top, true),
-            (Edge::Bottom, false),
-        ] {
-            widgets.window.set_anchor(anchor, state);
-        }
+        set_position(&widgets.window, config.position);
 
         ComponentParts { model, widgets }
     }
@@ -143,24 +248,64 @@ impl SimpleComponent for ControlPanelModel {
 
         match msg {
             Input::ToggleDock => {
-                self.set_dock_enabled(!self.dock_enabled);
                 sender.output(Output::ToggleDock).unwrap();
             }
+            Input::UpdateDock(x) => {
+                self.set_dock_enabled(x);
+            }
             Input::Toggle => {
+                self.section = None;
                 self.set_visible(!self.visible);
             }
-            Input::UpdateBrightness(x) => {
-                self.brightness.emit(brightness::Input::Update(x));
+            Input::ToggleSection(x) => {
+                if self.visible && self.section == Some(x) {
+                    self.section = None;
+                    self.set_visible(false);
+                    return;
+                }
+                self.section = Some(x);
+                self.set_visible(true);
+                let widget = match x {
+                    Section::Volume => {
+                        self.mixer.set_expanded(true);
+                        self.volume.widget().clone().upcast::<gtk::Widget>()
+                    }
+                    Section::Brightness => self.displays.widget().clone().upcast(),
+                };
+                widget.child_focus(gtk::DirectionType::TabForward);
+            }
+            Input::UpdateDisplays(x) => {
+                let mut displays = self.displays.guard();
+                // as with streams, keep the sliders while the same displays are around
+                if displays
+                    .iter()
+                    .map(|y| &y.display.id)
+                    .eq(x.iter().map(|y| &y.id))
+                {
+                    for (i, y) in x.into_iter().enumerate() {
+                        displays.send(i, brightness::Input::Update(y));
+                    }
+                } else {
+                    displays.clear();
+                    for y in x {
+                        displays.push_back(y);
+                    }
+                }
+                let count = displays.len();
+                drop(displays);
+                self.set_displays_count(count);
             }
             Input::ReloadCSS => {
-                //set_global_css(&crate::util::load_css("resources/bar.css"));
+                sender.output(Output::ReloadCSS).unwrap();
             }
-            Input::SetBrightness(x) => {
-                sender.output(Output::SetBrightness(x)).unwrap();
+            Input::SetBrightness(id, x) => {
+                sender.output(Output::SetBrightness(id, x)).unwrap();
             }
             Input::ToggleTiling => {
-                self.set_tiling(!self.tiling);
-                sender.output(Output::ToggleTiling(self.tiling)).unwrap();
+                sender.output(Output::ToggleTiling(!self.tiling)).unwrap();
+            }
+            Input::UpdateTiling(x) => {
+                self.set_tiling(x);
             }
             Input::UpdateVolume(x) => {
                 self.volume.emit(volume::Input::Update(x));
@@ -168,19 +313,101 @@ impl SimpleComponent for ControlPanelModel {
             Input::SetVolume(x) => {
                 sender.output(Output::SetVolume(x)).unwrap();
             }
-            Input::ToggleNotifs => {
-                match Command::new("swaync-client")
-                    .arg(if self.notifs { "-dn" } else { "-df" })
-                    .output()
+            Input::ToggleMute => {
+                sender.output(Output::ToggleMute).unwrap();
+            }
+            Input::UpdateSinks(x, default) => {
+                self.volume.emit(volume::Input::UpdateDevices(x, default));
+            }
+            Input::SetDefaultSink(x) => {
+                sender.output(Output::SetDefaultSink(x)).unwrap();
+            }
+            Input::UpdateMicrophone(x) => {
+                self.microphone.emit(volume::Input::Update(x));
+            }
+            Input::SetMicrophoneVolume(x) => {
+                sender.output(Output::SetMicrophoneVolume(x)).unwrap();
+            }
+            Input::ToggleMicrophoneMute => {
+                sender.output(Output::ToggleMicrophoneMute).unwrap();
+            }
+            Input::UpdateSources(x, default) => {
+                self.microphone
+                    .emit(volume::Input::UpdateDevices(x, default));
+            }
+            Input::SetDefaultSource(x) => {
+                sender.output(Output::SetDefaultSource(x)).unwrap();
+            }
+            Input::UpdateStreams(x) => {
+                let mut streams = self.streams.guard();
+                // update the rows in place while the same streams are playing,
+                // so a slider being dragged isn't replaced
+                if streams
+                    .iter()
+                    .map(|y| y.stream.index)
+                    .eq(x.iter().map(|y| y.index))
                 {
-                    Ok(_) => {
-                        self.set_notifs(!self.notifs);
+                    for (i, y) in x.into_iter().enumerate() {
+                        streams.send(i, stream::Input::Update(y));
                     }
-                    Err(e) => {
-                        log::error!("Failed to toggle notifications: {e}");
+                } else {
+                    streams.clear();
+                    for y in x {
+                        streams.push_back((y, self.overrides.clone(), self.audio.clone()));
                     }
                 }
+                let count = streams.len();
+                drop(streams);
+                self.set_streams_count(count);
+            }
+            Input::SetStreamVolume(index, x) => {
+                sender.output(Output::SetStreamVolume(index, x)).unwrap();
+            }
+            Input::SetStreamMute(index, x) => {
+                sender.output(Output::SetStreamMute(index, x)).unwrap();
+            }
+            Input::ToggleNotifs => {
+                sender.output(Output::ToggleNotifs).unwrap();
+            }
+            Input::UpdateNotifs(x) => {
+                self.set_notifs(x);
+            }
+            Input::UpdateConfig(x, overrides, audio) => {
+                set_position(&self.window, x.position);
+                if audio != self.audio {
+                    self.audio = audio;
+                    self.volume
+                        .emit(volume::Input::UpdateConfig(self.audio.clone()));
+                    self.microphone
+                        .emit(volume::Input::UpdateConfig(self.audio.clone()));
+                    self.streams
+                        .guard()
+                        .broadcast(stream::Input::UpdateConfig(self.audio.clone()));
+                }
+                if overrides != self.overrides {
+                    self.overrides = overrides;
+                    self.streams
+                        .guard()
+                        .broadcast(stream::Input::ApplyOverrides(self.overrides.clone()));
+                }
             }
         }
     }
 }
+
+fn set_position(window: &gtk::Window, position: Corner) {
+    let (top, left) = match position {
+        Corner::TopLeft => (true, true),
+        Corner::TopRight => (true, false),
+        Corner::BottomLeft => (false, true),
+        Corner::BottomRight => (false, false),
+    };
+    for (anchor, state) in [
+        (Edge::Left, left),
+        (Edge::Right, !left),
+        (Edge::Top, top),
+        (Edge::Bottom, !top),
+    ] {
+        window.set_anchor(anchor, state);
+    }
+}
@@ -0,0 +1,137 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+
+use crate::config::{AudioConfig, Overrides};
+use crate::workers::audio_worker::Stream;
+
+pub struct StreamModel {
+    pub stream: Stream,
+    icon: String,
+    config: AudioConfig,
+}
+
+#[derive(Debug)]
+pub enum Output {
+    SetVolume(u32, f64),
+    SetMute(u32, bool),
+}
+
+#[derive(Debug, Clone)]
+pub enum Input {
+    Update(Stream),
+    Changed(f64),
+    ToggleMute,
+    ApplyOverrides(Overrides),
+    UpdateConfig(AudioConfig),
+}
+
+#[relm4::factory(pub async)]
+impl AsyncFactoryComponent for StreamModel {
+    type Init = (Stream, Overrides, AudioConfig);
+    type Input = Input;
+    type Output = Output;
+    type CommandOutput = ();
+    type ParentWidget = gtk::Box;
+
+    view! {
+        #[root]
+        gtk::Box {
+            add_css_class: "stream",
+            set_spacing: 4,
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Box {
+                set_spacing: 4,
+
+                gtk::Image {
+                    #[watch]
+                    set_icon_name: Some(&self.icon),
+                },
+                gtk::Label {
+                    set_hexpand: true,
+                    set_halign: gtk::Align::Start,
+                    set_ellipsize: gtk::pango::EllipsizeMode::End,
+                    #[watch]
+                    set_text: &self.stream.name,
+                },
+            },
+
+            gtk::Box {
+                set_spacing: 4,
+
+                gtk::Button {
+                    add_css_class: "info_button",
+                    #[watch]
+                    set_class_active: ("active", self.stream.volume.muted),
+                    #[watch]
+                    set_icon_name: if self.stream.volume.muted {
+                        "audio-volume-muted-symbolic"
+                    } else {
+                        "audio-volume-high-symbolic"
+                    },
+                    connect_clicked => Input::ToggleMute,
+                },
+
+                gtk::Scale {
+                    set_hexpand: true,
+                    add_mark: (100.0, gtk::PositionType::Bottom, None),
+                    #[watch]
+                    set_range: (0.0, self.config.max_volume),
+                    #[watch]
+                    set_increments: (self.config.step, self.config.step * 2.0),
+
+                    // only the user moving the slider should change the volume
+                    #[watch]
+                    #[block_signal(value_handler)]
+                    set_value: self.stream.volume.level,
+
+                    connect_value_changed[sender] => move |x| {
+                        sender.input(Input::Changed(x.value()))
+                    } @value_handler,
+                }
+            }
+        }
+    }
+
+    async fn init_model(
+        init: Self::Init,
+        _index: &DynamicIndex,
+        _sender: AsyncFactorySender<Self>,
+    ) -> Self {
+        Self {
+            icon: init.1.apply(init.0.icon.clone()),
+            stream: init.0,
+            config: init.2,
+        }
+    }
+
+    async fn update(&mut self, msg: Self::Input, sender: AsyncFactorySender<Self>) {
+        match msg {
+            Input::Update(x) => {
+                self.stream = x;
+            }
+            Input::Changed(x) => {
+                sender
+                    .output(Output::SetVolume(
+                        self.stream.index,
+                        super::volume::snap(x, &self.config),
+                    ))
+                    .unwrap();
+            }
+            Input::ToggleMute => {
+                sender
+                    .output(Output::SetMute(
+                        self.stream.index,
+                        !self.stream.volume.muted,
+                    ))
+                    .unwrap();
+            }
+            Input::ApplyOverrides(x) => {
+                self.icon = x.apply(self.stream.icon.clone());
+            }
+            Input::UpdateConfig(x) => {
+                self.config = x;
+            }
+        }
+    }
+}
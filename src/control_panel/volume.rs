@@ -1,25 +1,41 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
+use crate::config::AudioConfig;
+use crate::workers::audio_worker::{Device, Kind, Volume};
+
 #[tracker::track]
 pub struct VolumeModel {
+    #[tracker::do_not_track]
+    kind: Kind,
+    config: AudioConfig,
     volume: f64,
+    muted: bool,
+    devices: Vec<Device>,
+    #[tracker::do_not_track]
+    default_device: String,
 }
 
 #[derive(Debug)]
 pub enum Input {
-    Update(f64),
+    Update(Volume),
     Changed(f64),
+    ToggleMute,
+    UpdateDevices(Vec<Device>, String),
+    SelectDevice(u32),
+    UpdateConfig(AudioConfig),
 }
 
 #[derive(Debug)]
 pub enum Output {
     SetVolume(f64),
+    ToggleMute,
+    SetDefault(String),
 }
 
 #[relm4::component(pub async)]
 impl AsyncComponent for VolumeModel {
-    type Init = ();
+    type Init = (Kind, AudioConfig);
     type Input = Input;
     type Output = Output;
     type CommandOutput = ();
@@ -33,30 +49,84 @@ impl AsyncComponent for VolumeModel {
 
             gtk::Label {
                 set_halign: gtk::Align::Center,
-                set_text: "Volume"
+                set_text: match model.kind {
+                    Kind::Output => "Volume",
+                    Kind::Input => "Microphone",
+                },
             },
 
-            gtk::Scale {
-                set_halign: gtk::Align::Fill,
-                set_range: (0.0, 100.0),
+            gtk::DropDown {
+                #[track = "model.changed_devices()"]
+                set_visible: !model.devices.is_empty(),
+                // replacing the list shouldn't change the default device
+                #[track = "model.changed_devices()"]
+                #[block_signal(selected_handler)]
+                set_model: Some(&device_list(&model.devices)),
+                #[track = "model.changed_devices()"]
+                #[block_signal(selected_handler)]
+                set_selected: model
+                    .devices
+                    .iter()
+                    .position(|x| x.name == model.default_device)
+                    .map_or(gtk::INVALID_LIST_POSITION, |x| x as u32),
 
-                #[track = "model.changed_volume()"]
-                set_value: model.volume,
+                connect_selected_notify[sender] => move |x| {
+                    sender.input(Input::SelectDevice(x.selected()))
+                } @selected_handler,
+            },
 
-                connect_value_changed[sender] => move |x| {
-                    sender.input(Input::Changed(x.value()))
+            gtk::Box {
+                set_spacing: 4,
+
+                gtk::Button {
+                    add_css_class: "info_button",
+                    #[track = "model.changed_muted()"]
+                    set_class_active: ("active", model.muted),
+                    #[track = "model.changed_muted()"]
+                    set_icon_name: match (model.kind, model.muted) {
+                        (Kind::Output, true) => "audio-volume-muted-symbolic",
+                        (Kind::Output, false) => "audio-volume-high-symbolic",
+                        (Kind::Input, true) => "microphone-sensitivity-muted-symbolic",
+                        (Kind::Input, false) => "audio-input-microphone-symbolic",
+                    },
+                    connect_clicked => Input::ToggleMute,
                 },
+
+                gtk::Scale {
+                    set_hexpand: true,
+                    add_mark: (100.0, gtk::PositionType::Bottom, None),
+                    // a smaller range clamps the value, which isn't the user moving it
+                    #[track = "model.changed_config()"]
+                    #[block_signal(value_handler)]
+                    set_range: (0.0, model.config.max_volume),
+                    #[track = "model.changed_config()"]
+                    set_increments: (model.config.step, model.config.step * 2.0),
+
+                    // only the user moving the slider should change the volume
+                    #[track = "model.changed_volume()"]
+                    #[block_signal(value_handler)]
+                    set_value: model.volume,
+
+                    connect_value_changed[sender] => move |x| {
+                        sender.input(Input::Changed(x.value()))
+                    } @value_handler,
+                }
             }
         }
     }
 
     async fn init(
-        _init: Self::Init,
+        (kind, config): Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
         let model = VolumeModel {
+            kind,
+            config,
             volume: 0.0,
+            muted: false,
+            devices: Vec::new(),
+            default_device: String::new(),
             tracker: 0,
         };
 
@@ -75,11 +145,58 @@ impl AsyncComponent for VolumeModel {
 
         match msg {
             Input::Update(x) => {
-                self.set_volume(x);
+                self.set_volume(x.level);
+                self.set_muted(x.muted);
             }
             Input::Changed(x) => {
-                sender.output(Output::SetVolume(x)).unwrap();
+                sender
+                    .output(Output::SetVolume(snap(x, &self.config)))
+                    .unwrap();
+            }
+            Input::ToggleMute => {
+                sender.output(Output::ToggleMute).unwrap();
+            }
+            Input::UpdateDevices(x, default) => {
+                self.default_device = default;
+                self.set_devices(x);
+            }
+            Input::SelectDevice(x) => {
+                if let Some(device) = self.devices.get(x as usize) {
+                    if device.name != self.default_device {
+                        sender
+                            .output(Output::SetDefault(device.name.clone()))
+                            .unwrap();
+                    }
+                }
+            }
+            Input::UpdateConfig(x) => {
+                self.set_config(x);
             }
         }
     }
 }
+
+/// Pulls levels close to 100% onto it, so the normal volume is easy to hit when boosting.
+pub fn snap(level: f64, config: &AudioConfig) -> f64 {
+    if config.max_volume > 100.0 && (level - 100.0).abs() < config.max_volume / 50.0 {
+        100.0
+    } else {
+        level
+    }
+}
+
+fn device_list(devices: &[Device]) -> gtk::StringList {
+    let list = gtk::StringList::new(&[]);
+    for x in devices {
+        list.append(&label(x));
+    }
+    list
+}
+
+/// Names a device by its description and the port in use, like "Built-in Audio (Headphones)".
+fn label(device: &Device) -> String {
+    match &device.active_port {
+        Some(port) if device.ports.len() > 1 => format!("{} ({port})", device.description),
+        _ => device.description.clone(),
+    }
+}
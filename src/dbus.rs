@@ -0,0 +1,499 @@
+//! The `org.poach3r.swirly` object on the session bus.
+//!
+//! Methods and property writes are turned into the same requests as the command socket,
+//! so they behave exactly like `swirly-msg`.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::future::BoxFuture;
+use zbus::{blocking::Connection, fdo, names::BusName, zvariant::Value};
+
+use crate::ipc::{Request, Response, State};
+
+pub const NAME: &str = "org.poach3r.swirly";
+pub const PATH: &str = "/org/poach3r/swirly";
+
+/// Hands a request to swirly, resolving once it's answered.
+///
+/// zbus runs each call as its own task, so a slow answer only holds up the client waiting for it.
+pub type Handler = Box<dyn Fn(Request) -> BoxFuture<'static, Response> + Send + Sync>;
+
+pub struct Swirly {
+    state: Arc<Mutex<State>>,
+    handler: Handler,
+}
+
+impl Swirly {
+    async fn request(&self, request: Request) -> fdo::Result<()> {
+        match (self.handler)(request).await {
+            Response::Error { message } => Err(fdo::Error::Failed(message)),
+            _ => Ok(()),
+        }
+    }
+
+    fn state(&self) -> State {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Records a value written through a property before swirly reports it,
+    /// so the `PropertiesChanged` signal sent for the write carries the new value.
+    fn assume<F: FnOnce(&mut State)>(&self, update: F) {
+        update(&mut self.state.lock().unwrap());
+    }
+}
+
+#[zbus::interface(name = "org.poach3r.swirly")]
+impl Swirly {
+    async fn toggle_dock(&self) -> fdo::Result<()> {
+        self.request(Request::ToggleDock).await
+    }
+
+    async fn toggle_control_panel(&self) -> fdo::Result<()> {
+        self.request(Request::ToggleControlPanel).await
+    }
+
+    async fn toggle_tiling(&self) -> fdo::Result<()> {
+        self.request(Request::ToggleTiling).await
+    }
+
+    async fn toggle_notifications(&self) -> fdo::Result<()> {
+        self.request(Request::ToggleNotifs).await
+    }
+
+    async fn reload_css(&self) -> fdo::Result<()> {
+        self.request(Request::ReloadCss).await
+    }
+
+    async fn toggle_mute(&self) -> fdo::Result<()> {
+        self.request(Request::ToggleMute).await
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.state().volume
+    }
+
+    #[zbus(property)]
+    async fn set_volume(&self, volume: f64) -> fdo::Result<()> {
+        self.request(Request::SetVolume { volume }).await?;
+        self.assume(|x| x.volume = volume);
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn muted(&self) -> bool {
+        self.state().muted
+    }
+
+    #[zbus(property)]
+    async fn set_muted(&self, muted: bool) -> fdo::Result<()> {
+        self.request(Request::SetMute { muted }).await?;
+        self.assume(|x| x.muted = muted);
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn brightness(&self) -> u32 {
+        self.state().brightness
+    }
+
+    #[zbus(property)]
+    async fn set_brightness(&self, brightness: u32) -> fdo::Result<()> {
+        self.request(Request::SetBrightness {
+            brightness,
+            fade: None,
+        })
+        .await?;
+        self.assume(|x| x.brightness = brightness);
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn battery(&self) -> f64 {
+        self.state().battery as f64
+    }
+
+    #[zbus(property)]
+    fn dock_enabled(&self) -> bool {
+        self.state().dock_enabled
+    }
+
+    #[zbus(property)]
+    async fn set_dock_enabled(&self, enabled: bool) -> fdo::Result<()> {
+        if enabled != self.state().dock_enabled {
+            self.request(Request::ToggleDock).await?;
+            self.assume(|x| x.dock_enabled = enabled);
+        }
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn tiling(&self) -> bool {
+        self.state().tiling
+    }
+
+    #[zbus(property)]
+    async fn set_tiling(&self, tiling: bool) -> fdo::Result<()> {
+        self.request(Request::SetTiling { tiling }).await?;
+        self.assume(|x| x.tiling = tiling);
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn notifications(&self) -> bool {
+        self.state().notifications
+    }
+
+    #[zbus(property)]
+    async fn set_notifications(&self, enabled: bool) -> fdo::Result<()> {
+        if enabled != self.state().notifications {
+            self.request(Request::ToggleNotifs).await?;
+            self.assume(|x| x.notifications = enabled);
+        }
+        Ok(())
+    }
+}
+
+pub struct Service {
+    connection: Connection,
+    state: Arc<Mutex<State>>,
+}
+
+impl Service {
+    /// Serves the interface on the session bus.
+    pub fn session(handler: Handler) -> zbus::Result<Self> {
+        Self::serve(zbus::blocking::connection::Builder::session()?, handler)
+    }
+
+    /// Serves the interface on the bus at `address`, such as a private `dbus-daemon`.
+    pub fn address(address: &str, handler: Handler) -> zbus::Result<Self> {
+        Self::serve(
+            zbus::blocking::connection::Builder::address(address)?,
+            handler,
+        )
+    }
+
+    fn serve(
+        builder: zbus::blocking::connection::Builder<'_>,
+        handler: Handler,
+    ) -> zbus::Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let connection = builder
+            .name(NAME)?
+            .serve_at(
+                PATH,
+                Swirly {
+                    state: state.clone(),
+                    handler,
+                },
+            )?
+            .build()?;
+
+        Ok(Self { connection, state })
+    }
+
+    /// Replaces the exposed state, sending `PropertiesChanged` for whatever differs.
+    pub fn update(&self, new: State) -> zbus::Result<()> {
+        let mut changed: HashMap<&str, Value> = HashMap::new();
+        {
+            let mut old = self.state.lock().unwrap();
+            if old.volume != new.volume {
+                changed.insert("Volume", Value::from(new.volume));
+            }
+            if old.muted != new.muted {
+                changed.insert("Muted", Value::from(new.muted));
+            }
+            if old.brightness != new.brightness {
+                changed.insert("Brightness", Value::from(new.brightness));
+            }
+            if old.battery != new.battery {
+                changed.insert("Battery", Value::from(new.battery as f64));
+            }
+            if old.dock_enabled != new.dock_enabled {
+                changed.insert("DockEnabled", Value::from(new.dock_enabled));
+            }
+            if old.tiling != new.tiling {
+                changed.insert("Tiling", Value::from(new.tiling));
+            }
+            if old.notifications != new.notifications {
+                changed.insert("Notifications", Value::from(new.notifications));
+            }
+            *old = new;
+        }
+
+        if changed.is_empty() {
+            return Ok(());
+        }
+        self.connection.emit_signal(
+            None::<BusName>,
+            PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            &(NAME, changed, Vec::<&str>::new()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    use zbus::{
+        blocking::{connection, MessageIterator},
+        zvariant::OwnedValue,
+        MatchRule,
+    };
+
+    use super::*;
+
+    /// A `dbus-daemon` of the test's own, stopped when it's dropped.
+    struct Daemon {
+        child: Child,
+        address: String,
+    }
+
+    impl Daemon {
+        fn start() -> Self {
+            let mut child = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("dbus-daemon should be installed");
+            let mut address = String::new();
+            BufReader::new(child.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            Self {
+                child,
+                address: address.trim().to_string(),
+            }
+        }
+    }
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    /// Serves the interface on a private bus, recording every request it's handed.
+    fn serve(daemon: &Daemon) -> (Service, mpsc::Receiver<Request>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let handler: Handler = Box::new(move |request| {
+            let response = match request {
+                Request::SetVolume { volume } if volume > 100.0 => Response::error("too loud"),
+                _ => Response::Ok,
+            };
+            tx.lock().unwrap().send(request).unwrap();
+            Box::pin(async move { response })
+        });
+        let service = Service::address(&daemon.address, handler).unwrap();
+        (service, rx)
+    }
+
+    fn client(daemon: &Daemon) -> Connection {
+        connection::Builder::address(daemon.address.as_str())
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn get(client: &Connection, property: &str) -> OwnedValue {
+        client
+            .call_method(
+                Some(NAME),
+                PATH,
+                Some("org.freedesktop.DBus.Properties"),
+                "Get",
+                &(NAME, property),
+            )
+            .unwrap()
+            .body()
+            .deserialize()
+            .unwrap()
+    }
+
+    fn set(client: &Connection, property: &str, value: Value) -> zbus::Result<()> {
+        client
+            .call_method(
+                Some(NAME),
+                PATH,
+                Some("org.freedesktop.DBus.Properties"),
+                "Set",
+                &(NAME, property, value),
+            )
+            .map(|_| ())
+    }
+
+    #[test]
+    fn methods_become_requests() {
+        let daemon = Daemon::start();
+        let (_service, requests) = serve(&daemon);
+        let client = client(&daemon);
+
+        for (method, request) in [
+            ("ToggleDock", Request::ToggleDock),
+            ("ToggleControlPanel", Request::ToggleControlPanel),
+            ("ToggleTiling", Request::ToggleTiling),
+            ("ToggleNotifications", Request::ToggleNotifs),
+            ("ReloadCss", Request::ReloadCss),
+            ("ToggleMute", Request::ToggleMute),
+        ] {
+            client
+                .call_method(Some(NAME), PATH, Some(NAME), method, &())
+                .unwrap();
+            assert_eq!(requests.try_recv().unwrap(), request, "{method}");
+        }
+    }
+
+    #[test]
+    fn properties_read_the_state() {
+        let daemon = Daemon::start();
+        let (service, _requests) = serve(&daemon);
+        let client = client(&daemon);
+
+        service
+            .update(State {
+                volume: 40.0,
+                muted: true,
+                brightness: 70,
+                battery: 55.0,
+                ..State::default()
+            })
+            .unwrap();
+
+        assert_eq!(f64::try_from(get(&client, "Volume")).unwrap(), 40.0);
+        assert!(bool::try_from(get(&client, "Muted")).unwrap());
+        assert_eq!(u32::try_from(get(&client, "Brightness")).unwrap(), 70);
+        assert_eq!(f64::try_from(get(&client, "Battery")).unwrap(), 55.0);
+        assert!(bool::try_from(get(&client, "DockEnabled")).unwrap());
+    }
+
+    #[test]
+    fn writing_properties_sends_requests() {
+        let daemon = Daemon::start();
+        let (_service, requests) = serve(&daemon);
+        let client = client(&daemon);
+
+        set(&client, "Volume", Value::from(40.0)).unwrap();
+        assert_eq!(
+            requests.try_recv().unwrap(),
+            Request::SetVolume { volume: 40.0 }
+        );
+
+        set(&client, "Brightness", Value::from(30u32)).unwrap();
+        assert_eq!(
+            requests.try_recv().unwrap(),
+            Request::SetBrightness {
+                brightness: 30,
+                fade: None,
+            }
+        );
+
+        set(&client, "Tiling", Value::from(false)).unwrap();
+        assert_eq!(
+            requests.try_recv().unwrap(),
+            Request::SetTiling { tiling: false }
+        );
+
+        // the dock is only toggled when it's asked to change
+        set(&client, "DockEnabled", Value::from(true)).unwrap();
+        assert!(requests.try_recv().is_err());
+        set(&client, "DockEnabled", Value::from(false)).unwrap();
+        assert_eq!(requests.try_recv().unwrap(), Request::ToggleDock);
+
+        // errors from swirly reach the caller
+        assert!(set(&client, "Volume", Value::from(150.0)).is_err());
+    }
+
+    #[test]
+    fn updates_send_properties_changed() {
+        let daemon = Daemon::start();
+        let (service, _requests) = serve(&daemon);
+        let client = client(&daemon);
+
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface("org.freedesktop.DBus.Properties")
+            .unwrap()
+            .member("PropertiesChanged")
+            .unwrap()
+            .path(PATH)
+            .unwrap()
+            .build();
+        let signals = MessageIterator::for_match_rule(rule, &client, None).unwrap();
+
+        // the iterator blocks, so it's read elsewhere to give up after a while
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for message in signals {
+                let body = message.unwrap().body();
+                let (_, changed, _): (String, HashMap<String, OwnedValue>, Vec<String>) =
+                    body.deserialize().unwrap();
+                if tx.send(changed).is_err() {
+                    return;
+                }
+            }
+        });
+
+        service
+            .update(State {
+                brightness: 70,
+                ..State::default()
+            })
+            .unwrap();
+
+        let changed = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(u32::try_from(&changed["Brightness"]).unwrap(), 70);
+    }
+
+    #[test]
+    fn slow_replies_only_hold_up_their_own_caller() {
+        let daemon = Daemon::start();
+        // the dock isn't answered until the test says so
+        let (release, gate) = flume::bounded::<()>(1);
+        let handler: Handler = Box::new(move |request| {
+            let gate = gate.clone();
+            Box::pin(async move {
+                if request == Request::ToggleDock {
+                    let _ = gate.recv_async().await;
+                }
+                Response::Ok
+            })
+        });
+        let _service = Service::address(&daemon.address, handler).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn({
+            let client = client(&daemon);
+            move || {
+                let result = client.call_method(Some(NAME), PATH, Some(NAME), "ToggleDock", &());
+                let _ = tx.send(result.is_ok());
+            }
+        });
+        thread::sleep(Duration::from_millis(100));
+
+        let client = client(&daemon);
+        client
+            .call_method(Some(NAME), PATH, Some(NAME), "ToggleMute", &())
+            .unwrap();
+        set(&client, "Volume", Value::from(40.0)).unwrap();
+        assert_eq!(f64::try_from(get(&client, "Volume")).unwrap(), 40.0);
+        assert!(rx.try_recv().is_err());
+
+        release.send(()).unwrap();
+        assert!(rx.recv_timeout(Duration::from_secs(5)).unwrap());
+    }
+}
@@ -1,8 +1,11 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
+use crate::config::Overrides;
+
 pub struct AppModel {
     pub id: i64,
+    app_name: String,
     name: String,
     focused: bool,
 }
@@ -16,11 +19,12 @@ pub enum Output {
 pub enum Input {
     Focus,
     Unfocus,
+    ApplyOverrides(Overrides),
 }
 
 #[relm4::factory(pub async)]
 impl AsyncFactoryComponent for AppModel {
-    type Init = (i64, String, bool);
+    type Init = (i64, String, Overrides, bool);
     type Input = Input;
     type Output = Output;
     type CommandOutput = ();
@@ -35,6 +39,7 @@ impl AsyncFactoryComponent for AppModel {
             set_valign: gtk::Align::Center,
             connect_clicked => Input::Focus,
             gtk::Image {
+                #[watch]
                 set_icon_name: Some(&self.name),
                 set_icon_size: gtk::IconSize::Large,
             }
@@ -48,8 +53,9 @@ impl AsyncFactoryComponent for AppModel {
     ) -> Self {
         Self {
             id: init.0,
-            name: init.1,
-            focused: init.2,
+            name: init.2.apply(init.1.clone()),
+            app_name: init.1,
+            focused: init.3,
         }
     }
 
@@ -61,6 +67,9 @@ impl AsyncFactoryComponent for AppModel {
             Input::Unfocus => {
                 self.focused = false;
             }
+            Input::ApplyOverrides(x) => {
+                self.name = x.apply(self.app_name.clone());
+            }
         }
     }
 }
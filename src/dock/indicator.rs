@@ -1,4 +1,4 @@
-use gtk::prelude::*;
+use gtk::{gdk, prelude::*};
 use gtk4_layer_shell::{Edge, Layer, LayerShell};
 use relm4::prelude::*;
 
@@ -20,7 +20,7 @@ pub enum Output {
 
 #[relm4::component(pub)]
 impl SimpleComponent for IndicatorModel {
-    type Init = ();
+    type Init = Option<gdk::Monitor>;
     type Input = Input;
     type Output = Output;
 
@@ -43,7 +43,7 @@ impl SimpleComponent for IndicatorModel {
     }
 
     fn init(
-        _init: Self::Init,
+        monitor: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -52,6 +52,9 @@ impl SimpleComponent for IndicatorModel {
 
         widgets.window.init_layer_shell();
         widgets.window.set_layer(Layer::Top);
+        if let Some(x) = &monitor {
+            widgets.window.set_monitor(x);
+        }
         for (anchor, state) in [
             (Edge::Left, false),
             (Edge::Right, false),
@@ -76,4 +79,8 @@ impl SimpleComponent for IndicatorModel {
             }
         }
     }
+
+    fn shutdown(&mut self, widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        widgets.window.destroy();
+    }
 }
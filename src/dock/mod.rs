@@ -2,24 +2,12 @@ mod app;
 mod indicator;
 mod launchable;
 
-use std::{fs::File, io::Read};
-
-use gtk::prelude::*;
+use gtk::{gdk, prelude::*};
 use gtk4_layer_shell::{Edge, Layer, LayerShell};
 use relm4::prelude::*;
 use swayipc::{Connection, Node, WindowChange, WindowEvent};
 
-#[derive(serde::Deserialize, Clone)]
-struct Overrides {
-    original_names: Vec<String>,
-    replacement_names: Vec<String>,
-}
-
-#[derive(serde::Deserialize, Clone)]
-struct Launchables {
-    icons: Vec<String>,
-    commands: Vec<String>,
-}
+use crate::config::{DockConfig, Overrides};
 
 #[tracker::track]
 pub struct DockModel {
@@ -34,11 +22,11 @@ pub struct DockModel {
     #[tracker::do_not_track]
     theme: gtk::IconTheme,
     #[tracker::do_not_track]
-    overrides: Option<Overrides>,
+    overrides: Overrides,
     apps_count: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Input {
     Init,
     Enter,
@@ -47,6 +35,7 @@ pub enum Input {
     Update(Box<WindowEvent>),
     Focus(i64),
     Launch(String),
+    UpdateConfig(DockConfig),
 }
 
 #[derive(Debug)]
@@ -57,7 +46,7 @@ pub enum Output {
 
 #[relm4::component(pub)]
 impl SimpleComponent for DockModel {
-    type Init = ();
+    type Init = (DockConfig, Option<gdk::Monitor>);
     type Input = Input;
     type Output = Output;
 
@@ -92,7 +81,7 @@ impl SimpleComponent for DockModel {
     }
 
     fn init(
-        _init: Self::Init,
+        (config, monitor): Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -107,22 +96,18 @@ impl SimpleComponent for DockModel {
             .forward(sender.input_sender(), |msg| match msg {
                 launchable::Output::Launch(x) => Input::Launch(x),
             });
-        if let Some(x) = load_launchables() {
-            for (i, y) in x.icons.iter().enumerate() {
-                launchables
-                    .guard()
-                    .push_back((y.to_owned(), x.commands[i].clone()));
-            }
+        for x in config.launchables {
+            launchables.guard().push_back((x.icon, x.command));
         }
 
         let indicator_builder = indicator::IndicatorModel::builder();
         relm4::main_application().add_window(&indicator_builder.root);
-        let indicator =
-            indicator_builder
-                .launch(())
-                .forward(sender.input_sender(), |msg| match msg {
-                    indicator::Output::Enter => Input::Enter,
-                });
+        let indicator = indicator_builder.launch(monitor.clone()).forward(
+            sender.input_sender(),
+            |msg| match msg {
+                indicator::Output::Enter => Input::Enter,
+            },
+        );
 
         let model = DockModel {
             enabled: true,
@@ -131,7 +116,7 @@ impl SimpleComponent for DockModel {
             launchables,
             indicator,
             theme: gtk::IconTheme::for_display(&gtk::gdk::Display::default().unwrap()),
-            overrides: load_overrides(),
+            overrides: config.overrides,
             apps_count: 0,
             tracker: 0,
         };
@@ -142,6 +127,9 @@ impl SimpleComponent for DockModel {
 
         widgets.window.init_layer_shell();
         widgets.window.set_layer(Layer::Top);
+        if let Some(x) = &monitor {
+            widgets.window.set_monitor(x);
+        }
         for (anchor, state) in [
             (Edge::Left, false),
             (Edge::Right, false),
@@ -197,7 +185,8 @@ impl SimpleComponent for DockModel {
                     WindowChange::New => {
                         self.apps.guard().push_back((
                             x.container.id,
-                            get_name(&x.container, &self.overrides),
+                            get_name(&x.container),
+                            self.overrides.clone(),
                             x.container.focused,
                         ));
                     }
@@ -220,7 +209,8 @@ impl SimpleComponent for DockModel {
                             index,
                             (
                                 x.container.id,
-                                get_name(&x.container, &self.overrides),
+                                get_name(&x.container),
+                                self.overrides.clone(),
                                 x.container.focused,
                             ),
                         );
@@ -229,6 +219,21 @@ impl SimpleComponent for DockModel {
                 }
                 self.set_apps_count(self.apps.len());
             }
+            Input::UpdateConfig(x) => {
+                let mut launchables = self.launchables.guard();
+                launchables.clear();
+                for y in x.launchables {
+                    launchables.push_back((y.icon, y.command));
+                }
+                drop(launchables);
+
+                if x.overrides != self.overrides {
+                    self.overrides = x.overrides;
+                    self.apps
+                        .guard()
+                        .broadcast(app::Input::ApplyOverrides(self.overrides.clone()));
+                }
+            }
             Input::Init => {
                 self.apps.guard().clear();
                 let mut connection = Connection::new().unwrap();
@@ -243,14 +248,16 @@ impl SimpleComponent for DockModel {
                         for app in workspace.nodes.iter() {
                             self.apps.guard().push_back((
                                 app.id,
-                                get_name(app, &self.overrides),
+                                get_name(app),
+                                self.overrides.clone(),
                                 app.focused,
                             ));
                         }
                         for app in workspace.floating_nodes.iter() {
                             self.apps.guard().push_back((
                                 app.id,
-                                get_name(app, &self.overrides),
+                                get_name(app),
+                                self.overrides.clone(),
                                 app.focused,
                             ));
                         }
@@ -260,80 +267,15 @@ impl SimpleComponent for DockModel {
             }
         }
     }
-}
-
-fn load_overrides() -> Option<Overrides> {
-    let path = if let Some(x) = option_env!("XDG_CONFIG_HOME") {
-        format!("{x}/swirly/overrides.toml")
-    } else if let Some(x) = option_env!("HOME") {
-        format!("{x}/.config/swirly/overrides.toml")
-    } else {
-        log::error!("Failed to find overrides.");
-        return None;
-    };
-    let mut file = match File::open(path) {
-        Ok(x) => x,
-        Err(e) => {
-            log::error!("Failed to read overrides: {e}");
-            return None;
-        }
-    };
-
-    let mut buf = String::new();
-    match file.read_to_string(&mut buf) {
-        Ok(_) => (),
-        Err(e) => {
-            log::error!("Failed to read overrides: {e}");
-            return None;
-        }
-    }
 
-    match toml::from_str(&buf) {
-        Ok(x) => Some(x),
-        Err(e) => {
-            log::error!("Failed to parse overrides: {e}");
-            None
-        }
-    }
-}
-
-fn load_launchables() -> Option<Launchables> {
-    let path = if let Some(x) = option_env!("XDG_CONFIG_HOME") {
-        format!("{x}/swirly/launchables.toml")
-    } else if let Some(x) = option_env!("HOME") {
-        format!("{x}/.config/swirly/launchables.toml")
-    } else {
-        log::error!("Failed to find launchables.");
-        return None;
-    };
-    let mut file = match File::open(path) {
-        Ok(x) => x,
-        Err(e) => {
-            log::error!("Failed to read launchables: {e}");
-            return None;
-        }
-    };
-
-    let mut buf = String::new();
-    match file.read_to_string(&mut buf) {
-        Ok(_) => (),
-        Err(e) => {
-            log::error!("Failed to read launchables: {e}");
-            return None;
-        }
-    }
-
-    match toml::from_str(&buf) {
-        Ok(x) => Some(x),
-        Err(e) => {
-            log::error!("Failed to parse launchables: {e}");
-            None
-        }
+    fn shutdown(&mut self, widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        widgets.window.destroy();
     }
 }
 
-fn get_name(app: &Node, overrides: &Option<Overrides>) -> String {
-    let name = if let Some(id) = &app.app_id {
+/// Returns the app id or class of a window, before any overrides.
+pub fn get_name(app: &Node) -> String {
+    if let Some(id) = &app.app_id {
         id.to_string()
     } else if let Some(props) = &app.window_properties {
         props.class.clone().unwrap()
@@ -341,23 +283,5 @@ fn get_name(app: &Node, overrides: &Option<Overrides>) -> String {
         name.to_owned()
     } else {
         String::new() // should be unreachable but im not sure
-    };
-
-    if let Some(x) = overrides {
-        for (i, over) in x.original_names.iter().enumerate() {
-            if *over == name {
-                match x.replacement_names.get(i) {
-                    Some(x) => {
-                        return x.to_owned();
-                    }
-                    None => {
-                        log::error!("Failed to find matching override for {name}.");
-                        return name;
-                    }
-                }
-            }
-        }
     }
-
-    name
 }
@@ -0,0 +1,85 @@
+//! The protocol spoken over swirly's command socket.
+//!
+//! Each request and response is a single line of JSON.
+//! This module is shared with `swirly-msg`, so it must not depend on the rest of swirly.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Request {
+    ToggleDock,
+    ToggleControlPanel,
+    ToggleTiling,
+    SetTiling {
+        tiling: bool,
+    },
+    ToggleNotifs,
+    SetVolume {
+        volume: f64,
+    },
+    ToggleMute,
+    SetMute {
+        muted: bool,
+    },
+    SetBrightness {
+        brightness: u32,
+        /// Milliseconds to fade over instead of the configured time.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fade: Option<u64>,
+    },
+    ReloadCss,
+    Query,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    State(State),
+    Error { message: String },
+}
+
+impl Response {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::Error {
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct State {
+    pub volume: f64,
+    pub muted: bool,
+    pub brightness: u32,
+    pub battery: f32,
+    pub dock_enabled: bool,
+    pub tiling: bool,
+    pub notifications: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            volume: 0.0,
+            muted: false,
+            brightness: 0,
+            battery: 0.0,
+            dock_enabled: true,
+            tiling: true,
+            notifications: true,
+        }
+    }
+}
+
+/// Returns `SWIRLY_SOCK` or `XDG_RUNTIME_DIR/swirly.sock`.
+pub fn socket_path() -> Option<PathBuf> {
+    if let Some(x) = std::env::var_os("SWIRLY_SOCK") {
+        Some(PathBuf::from(x))
+    } else {
+        std::env::var_os("XDG_RUNTIME_DIR").map(|x| PathBuf::from(x).join("swirly.sock"))
+    }
+}
@@ -1,56 +1,97 @@
 #![allow(dead_code)]
 
 mod bar;
+mod config;
 mod control_panel;
+mod dbus;
 mod dock;
+mod ipc;
+mod osd;
+mod style;
 mod workers;
 
-use std::process::Command;
+use std::{collections::HashMap, process::Command, time::Duration};
 
+use config::Config;
 use env_logger::Env;
-use swayipc::WindowEvent;
+use swayipc::{WindowEvent, Workspace};
+use workers::audio_worker::{Device, Stream, Volume};
+use workers::battery_worker;
+use workers::brightness_worker::Display;
 
-use gtk::{glib::DateTime, prelude::*};
+use gtk::{gdk, glib::DateTime, prelude::*};
 use relm4::{prelude::*, set_global_css, WorkerController};
 
 struct AppModel {
-    brightness_mode: BrightnessMode,
-    bar: Controller<bar::BarModel>,
+    state: ipc::State,
+    config: Config,
+    style: style::UserStyle,
+    workspaces: Vec<Workspace>,
+    microphone: Volume,
+    recording: bool,
+    battery: battery_worker::Status,
+    /// One bar per monitor, keyed by connector name.
+    bars: HashMap<String, Controller<bar::BarModel>>,
     control_panel: Controller<control_panel::ControlPanelModel>,
-    dock: Controller<dock::DockModel>,
+    osd: Controller<osd::OsdModel>,
+    /// Docks keyed the same way as `bars`.
+    docks: HashMap<String, Controller<dock::DockModel>>,
     sway_worker: WorkerController<workers::sway_worker::AsyncHandler>,
     sway_executor: WorkerController<workers::sway_executor::AsyncHandler>,
     battery_worker: WorkerController<workers::battery_worker::AsyncHandler>,
     brightness_worker: WorkerController<workers::brightness_worker::AsyncHandler>,
     time_worker: WorkerController<workers::time_worker::AsyncHandler>,
     audio_worker: WorkerController<workers::audio_worker::AsyncHandler>,
-}
-
-enum BrightnessMode {
-    BrightnessCTL,
-    BacklightControl,
+    config_worker: WorkerController<workers::config_worker::AsyncHandler>,
+    ipc_worker: WorkerController<workers::ipc_worker::AsyncHandler>,
+    dbus_worker: WorkerController<workers::dbus_worker::AsyncHandler>,
 }
 
 #[derive(Debug)]
 pub enum Input {
     SetBrightness(u32),
+    SetDisplayBrightness(String, u32),
+    /// Raises the brightness by the configured step if true, otherwise lowers it.
+    StepBrightness(bool),
     UpdateBrightness(u32),
-    UpdateBattery(f32),
-    UpdateWorkspaces(i32),
+    UpdateDisplays(Vec<Display>),
+    UpdateBattery(battery_worker::Status),
+    UpdateWorkspaces(Vec<Workspace>),
     UpdateWindows(Box<WindowEvent>),
+    FocusEmptyWorkspace,
+    UpdateMonitors,
     UpdateTime(DateTime),
     ToggleControlPanel,
+    ShowControlPanel(control_panel::Section),
     ToggleDock,
     ToggleTiling(bool),
+    ToggleNotifs,
     FocusWindow(i64),
-    UpdateVolume(f64),
+    UpdateVolume(Volume),
     SetVolume(f64),
+    /// Raises the volume by the configured step if true, otherwise lowers it.
+    StepVolume(bool),
+    ToggleMute,
+    UpdateSinks(Vec<Device>, String),
+    SetDefaultSink(String),
+    UpdateMicrophone(Volume),
+    SetMicrophoneVolume(f64),
+    ToggleMicrophoneMute,
+    UpdateSources(Vec<Device>, String),
+    SetDefaultSource(String),
+    UpdateRecording(bool),
+    UpdateStreams(Vec<Stream>),
+    SetStreamVolume(u32, f64),
+    SetStreamMute(u32, bool),
     ArbitrarySwayMsg(String),
+    UpdateConfig(Box<Config>),
+    ReloadCSS,
+    Request(ipc::Request, flume::Sender<ipc::Response>),
 }
 
 #[relm4::component]
 impl SimpleComponent for AppModel {
-    type Init = ();
+    type Init = Config;
     type Input = Input;
     type Output = ();
 
@@ -61,161 +102,506 @@ impl SimpleComponent for AppModel {
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let sway_worker = workers::sway_worker::AsyncHandler::builder()
             .detach_worker(())
             .forward(sender.input_sender(), |msg| match msg {
-                workers::sway_worker::Output::UpdateWorkspaces(i) => Input::UpdateWorkspaces(i),
+                workers::sway_worker::Output::UpdateWorkspaces(x) => Input::UpdateWorkspaces(x),
                 workers::sway_worker::Output::UpdateWindows(x) => Input::UpdateWindows(x),
+                workers::sway_worker::Output::FocusEmptyWorkspace => Input::FocusEmptyWorkspace,
+                workers::sway_worker::Output::UpdateOutputs => Input::UpdateMonitors,
             });
         let sway_executor = workers::sway_executor::AsyncHandler::builder()
             .detach_worker(())
             .detach();
         let battery_worker = workers::battery_worker::AsyncHandler::builder()
-            .detach_worker(match get_battery() {
-                Ok(x) => Some(x),
-                Err(e) => {
-                    log::error!("Failed to find battery: {e}");
-                    None
-                }
-            })
+            .detach_worker(config.workers.battery.clone())
             .forward(sender.input_sender(), |msg| match msg {
-                workers::battery_worker::Output::UpdateLife(i) => Input::UpdateBattery(i),
+                workers::battery_worker::Output::Update(x) => Input::UpdateBattery(x),
             });
         let brightness_worker = workers::brightness_worker::AsyncHandler::builder()
-            .detach_worker(())
+            .detach_worker(config.workers.brightness.clone())
             .forward(sender.input_sender(), |msg| match msg {
                 workers::brightness_worker::Output::UpdateBrightness(x) => {
                     Input::UpdateBrightness(x)
                 }
+                workers::brightness_worker::Output::UpdateDisplays(x) => Input::UpdateDisplays(x),
             });
         let time_worker = workers::time_worker::AsyncHandler::builder()
-            .detach_worker(())
+            .detach_worker(config.workers.time.clone())
             .forward(sender.input_sender(), |msg| match msg {
                 workers::time_worker::Output::UpdateTime(x) => Input::UpdateTime(x),
             });
         let audio_worker = workers::audio_worker::AsyncHandler::builder()
-            .detach_worker(())
+            .detach_worker(config.workers.audio.clone())
             .forward(sender.input_sender(), |msg| match msg {
                 workers::audio_worker::Output::UpdateVolume(x) => Input::UpdateVolume(x),
+                workers::audio_worker::Output::UpdateSinks(x, default) => {
+                    Input::UpdateSinks(x, default)
+                }
+                workers::audio_worker::Output::UpdateMicrophone(x) => Input::UpdateMicrophone(x),
+                workers::audio_worker::Output::UpdateSources(x, default) => {
+                    Input::UpdateSources(x, default)
+                }
+                workers::audio_worker::Output::UpdateRecording(x) => Input::UpdateRecording(x),
+                workers::audio_worker::Output::UpdateStreams(x) => Input::UpdateStreams(x),
             });
-
-        let app = relm4::main_application();
-        let bar_builder = bar::BarModel::builder();
-        let control_panel_builder = control_panel::ControlPanelModel::builder();
-        let dock_builder = dock::DockModel::builder();
-        app.add_window(&bar_builder.root);
-        app.add_window(&control_panel_builder.root);
-        app.add_window(&dock_builder.root);
-
-        let bar = bar_builder
-            .launch(())
+        let config_worker = workers::config_worker::AsyncHandler::builder()
+            .detach_worker(())
             .forward(sender.input_sender(), |msg| match msg {
-                bar::Output::ToggleControlPanel => Input::ToggleControlPanel,
+                workers::config_worker::Output::UpdateConfig(x) => Input::UpdateConfig(x),
+                workers::config_worker::Output::UpdateCSS => Input::ReloadCSS,
             });
-        let control_panel =
-            control_panel_builder
-                .launch(())
-                .forward(sender.input_sender(), |msg| match msg {
-                    control_panel::Output::ToggleTiling(x) => Input::ToggleTiling(x),
-                    control_panel::Output::SetBrightness(x) => Input::SetBrightness(x),
-                    control_panel::Output::ToggleDock => Input::ToggleDock,
-                    control_panel::Output::SetVolume(x) => Input::SetVolume(x),
-                });
-        let dock = dock_builder
-            .launch(())
+        let ipc_worker = workers::ipc_worker::AsyncHandler::builder()
+            .detach_worker(())
+            .forward(sender.input_sender(), |msg| match msg {
+                workers::ipc_worker::Output::Request(x, reply) => Input::Request(x, reply),
+            });
+        let dbus_worker = workers::dbus_worker::AsyncHandler::builder()
+            .detach_worker(())
+            .forward(sender.input_sender(), |msg| match msg {
+                workers::dbus_worker::Output::Request(x, reply) => Input::Request(x, reply),
+            });
+
+        let control_panel_builder = control_panel::ControlPanelModel::builder();
+        relm4::main_application().add_window(&control_panel_builder.root);
+        let control_panel = control_panel_builder
+            .launch((
+                config.control_panel.clone(),
+                config.dock.overrides.clone(),
+                config.workers.audio.clone(),
+            ))
             .forward(sender.input_sender(), |msg| match msg {
-                dock::Output::Focus(x) => Input::FocusWindow(x),
-                dock::Output::Launch(x) => Input::ArbitrarySwayMsg(x),
+                control_panel::Output::ToggleTiling(x) => Input::ToggleTiling(x),
+                control_panel::Output::SetBrightness(id, x) => Input::SetDisplayBrightness(id, x),
+                control_panel::Output::ToggleDock => Input::ToggleDock,
+                control_panel::Output::SetVolume(x) => Input::SetVolume(x),
+                control_panel::Output::ToggleMute => Input::ToggleMute,
+                control_panel::Output::SetDefaultSink(x) => Input::SetDefaultSink(x),
+                control_panel::Output::SetMicrophoneVolume(x) => Input::SetMicrophoneVolume(x),
+                control_panel::Output::ToggleMicrophoneMute => Input::ToggleMicrophoneMute,
+                control_panel::Output::SetDefaultSource(x) => Input::SetDefaultSource(x),
+                control_panel::Output::SetStreamVolume(index, x) => {
+                    Input::SetStreamVolume(index, x)
+                }
+                control_panel::Output::SetStreamMute(index, x) => Input::SetStreamMute(index, x),
+                control_panel::Output::ReloadCSS => Input::ReloadCSS,
+                control_panel::Output::ToggleNotifs => Input::ToggleNotifs,
             });
 
-        let model = AppModel {
-            brightness_mode: BrightnessMode::BacklightControl,
-            bar,
+        let osd_builder = osd::OsdModel::builder();
+        relm4::main_application().add_window(&osd_builder.root);
+        let osd = osd_builder.launch(config.osd.clone()).detach();
+
+        let mut model = AppModel {
+            state: ipc::State::default(),
+            config,
+            style: style::UserStyle::new(),
+            workspaces: Vec::new(),
+            microphone: Volume::default(),
+            recording: false,
+            battery: battery_worker::Status::default(),
+            bars: HashMap::new(),
             control_panel,
-            dock,
+            osd,
+            docks: HashMap::new(),
             sway_worker,
             sway_executor,
             battery_worker,
             brightness_worker,
             time_worker,
             audio_worker,
+            config_worker,
+            ipc_worker,
+            dbus_worker,
         };
+        model.sync_monitors(&sender);
         let widgets = view_output!();
 
+        if let Some(display) = gdk::Display::default() {
+            let sender = sender.clone();
+            display
+                .monitors()
+                .connect_items_changed(move |_, _, _, _| sender.input(Input::UpdateMonitors));
+        }
+
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        let state = self.state.clone();
+
         match msg {
             Input::ToggleTiling(x) => {
+                self.state.tiling = x;
                 self.sway_executor
                     .emit(workers::sway_executor::Input::ToggleTiling(x));
+                self.control_panel
+                    .emit(control_panel::Input::UpdateTiling(x));
             }
-            Input::SetBrightness(x) => match self.brightness_mode {
-                BrightnessMode::BacklightControl => {
-                    match backlight_control_rs::adjust_brightness_absolute(x, true) {
-                        Ok(_) => (),
-                        Err(e) => {
-                            log::error!(
-                                "Failed to set brightness: {e}. Falling back on brightnessctl."
-                            );
-                            self.brightness_mode = BrightnessMode::BrightnessCTL
-                        }
-                    }
+            Input::ToggleNotifs => {
+                if let Err(e) = self.toggle_notifs() {
+                    log::error!("{e}");
                 }
-                BrightnessMode::BrightnessCTL => {
-                    match Command::new("brightnessctl")
-                        .args(["set", &format!("{x}%")])
-                        .output()
-                    {
-                        Ok(_) => (),
-                        Err(e) => {
-                            log::error!("Failed to set brightness with brightnessctl: {e}");
-                        }
-                    }
-                }
-            },
+            }
+            Input::SetBrightness(x) => {
+                self.brightness_worker
+                    .emit(workers::brightness_worker::Input::SetBrightness(
+                        x, None, None,
+                    ));
+            }
+            Input::SetDisplayBrightness(id, x) => {
+                self.brightness_worker.emit(
+                    workers::brightness_worker::Input::SetDisplayBrightness(id, x),
+                );
+            }
+            Input::StepBrightness(up) => {
+                let step = self.config.workers.brightness.step;
+                let brightness = match up {
+                    true => (self.state.brightness + step).min(100),
+                    false => self.state.brightness.saturating_sub(step),
+                };
+                sender.input(Input::SetBrightness(brightness));
+            }
             Input::UpdateBrightness(x) => {
-                self.bar.emit(bar::Input::UpdateBrightness(x));
+                self.state.brightness = x;
+                self.emit_bars(bar::Input::UpdateBrightness(x));
+                self.osd.emit(osd::Input::UpdateBrightness(x));
+            }
+            Input::UpdateDisplays(x) => {
                 self.control_panel
-                    .emit(control_panel::Input::UpdateBrightness(x))
+                    .emit(control_panel::Input::UpdateDisplays(x));
+            }
+            Input::UpdateBattery(x) => {
+                self.state.battery = x.percent;
+                self.emit_bars(bar::Input::UpdateBattery(x.clone()));
+                self.battery = x;
             }
-            Input::UpdateBattery(x) => self.bar.emit(bar::Input::UpdateBattery(x)),
             Input::UpdateWindows(x) => {
-                self.dock.emit(dock::Input::Update(x));
+                self.emit_bars(bar::Input::UpdateWindows(x.clone()));
+                self.emit_docks(dock::Input::Update(x));
+            }
+            Input::FocusEmptyWorkspace => {
+                self.emit_bars(bar::Input::FocusEmptyWorkspace);
             }
-            Input::UpdateWorkspaces(i) => {
-                self.bar.emit(bar::Input::UpdateWorkspaces(i));
+            Input::UpdateWorkspaces(x) => {
+                self.workspaces = x.clone();
+                self.emit_bars(bar::Input::UpdateWorkspaces(x));
             }
+            Input::UpdateMonitors => self.sync_monitors(&sender),
             Input::UpdateTime(x) => {
-                self.bar.emit(bar::Input::UpdateTime(x));
+                self.emit_bars(bar::Input::UpdateTime(x));
             }
             Input::ToggleControlPanel => self.control_panel.emit(control_panel::Input::Toggle),
-            Input::ToggleDock => self.dock.emit(dock::Input::Toggle),
+            Input::ShowControlPanel(x) => self
+                .control_panel
+                .emit(control_panel::Input::ToggleSection(x)),
+            Input::ToggleDock => {
+                self.state.dock_enabled = !self.state.dock_enabled;
+                self.emit_docks(dock::Input::Toggle);
+                self.control_panel
+                    .emit(control_panel::Input::UpdateDock(self.state.dock_enabled));
+            }
             Input::FocusWindow(x) => self
                 .sway_executor
                 .emit(workers::sway_executor::Input::Focus(x)),
             Input::UpdateVolume(x) => {
-                self.bar.emit(bar::Input::UpdateVolume(x));
+                self.state.volume = x.level;
+                self.state.muted = x.muted;
+                self.emit_bars(bar::Input::UpdateVolume(x));
                 self.control_panel
                     .emit(control_panel::Input::UpdateVolume(x));
+                self.osd.emit(osd::Input::UpdateVolume(x));
             }
             Input::SetVolume(x) => {
                 self.audio_worker
                     .emit(workers::audio_worker::Input::SetVolume(x));
             }
+            Input::StepVolume(up) => {
+                let audio = &self.config.workers.audio;
+                let step = if up { audio.step } else { -audio.step };
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::SetVolume(
+                        (self.state.volume + step).clamp(0.0, audio.max_volume),
+                    ));
+            }
+            Input::ToggleMute => {
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::ToggleMute);
+            }
+            Input::UpdateSinks(x, default) => {
+                self.control_panel
+                    .emit(control_panel::Input::UpdateSinks(x, default));
+            }
+            Input::SetDefaultSink(x) => {
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::SetDefaultSink(x));
+            }
+            Input::UpdateMicrophone(x) => {
+                self.microphone = x;
+                self.emit_bars(bar::Input::UpdateMicrophone(x));
+                self.control_panel
+                    .emit(control_panel::Input::UpdateMicrophone(x));
+            }
+            Input::SetMicrophoneVolume(x) => {
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::SetMicrophoneVolume(x));
+            }
+            Input::ToggleMicrophoneMute => {
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::ToggleMicrophoneMute);
+            }
+            Input::UpdateSources(x, default) => {
+                self.control_panel
+                    .emit(control_panel::Input::UpdateSources(x, default));
+            }
+            Input::SetDefaultSource(x) => {
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::SetDefaultSource(x));
+            }
+            Input::UpdateStreams(x) => {
+                self.control_panel
+                    .emit(control_panel::Input::UpdateStreams(x));
+            }
+            Input::SetStreamVolume(index, x) => {
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::SetStreamVolume(index, x));
+            }
+            Input::SetStreamMute(index, x) => {
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::SetStreamMute(index, x));
+            }
+            Input::UpdateRecording(x) => {
+                self.recording = x;
+                self.emit_bars(bar::Input::UpdateRecording(x));
+            }
             Input::ArbitrarySwayMsg(x) => {
                 self.sway_executor
                     .emit(workers::sway_executor::Input::ArbitrarySwayMsg(x));
             }
+            Input::UpdateConfig(x) => {
+                // the audio backend, the battery and time intervals, and the brightness
+                // worker's interval and paths are only read on startup
+                self.brightness_worker
+                    .emit(workers::brightness_worker::Input::UpdateConfig(
+                        x.workers.brightness.clone(),
+                    ));
+                self.emit_bars(bar::Input::UpdateConfig(
+                    x.bar.clone(),
+                    x.dock.overrides.clone(),
+                ));
+                self.osd.emit(osd::Input::UpdateConfig(x.osd.clone()));
+                self.control_panel.emit(control_panel::Input::UpdateConfig(
+                    x.control_panel.clone(),
+                    x.dock.overrides.clone(),
+                    x.workers.audio.clone(),
+                ));
+                self.audio_worker
+                    .emit(workers::audio_worker::Input::UpdateConfig(
+                        x.workers.audio.clone(),
+                    ));
+                self.emit_docks(dock::Input::UpdateConfig(x.dock.clone()));
+                // bars can't rearrange their modules in place, so start over
+                if x.bar.modules != self.config.bar.modules
+                    || x.bar.position.is_vertical() != self.config.bar.position.is_vertical()
+                {
+                    self.bars.clear();
+                }
+                self.config = *x;
+                // dock.all_outputs may have changed
+                self.sync_monitors(&sender);
+            }
+            Input::ReloadCSS => self.style.reload(),
+            Input::Request(ipc::Request::SetBrightness { brightness, fade }, reply)
+                if brightness <= 100 =>
+            {
+                // the worker replies once it's tried, so failures reach the client
+                self.brightness_worker
+                    .emit(workers::brightness_worker::Input::SetBrightness(
+                        brightness,
+                        fade.map(Duration::from_millis),
+                        Some(reply),
+                    ));
+            }
+            Input::Request(ipc::Request::ToggleNotifs, reply) => {
+                // swaync is asked right away, so the client learns whether it worked
+                let response = match self.toggle_notifs() {
+                    Ok(()) => ipc::Response::State(self.state.clone()),
+                    Err(e) => ipc::Response::error(e),
+                };
+                if reply.send(response).is_err() {
+                    log::warn!("Failed to reply to request, client is gone.");
+                }
+            }
+            Input::Request(x, reply) => {
+                let response = self.handle_request(x, &sender);
+                if reply.send(response).is_err() {
+                    log::warn!("Failed to reply to request, client is gone.");
+                }
+            }
+        }
+
+        if self.state != state {
+            self.dbus_worker
+                .emit(workers::dbus_worker::Input::UpdateState(self.state.clone()));
         }
     }
 }
 
+impl AppModel {
+    fn emit_bars(&self, msg: bar::Input) {
+        for x in self.bars.values() {
+            x.emit(msg.clone());
+        }
+    }
+
+    fn emit_docks(&self, msg: dock::Input) {
+        for x in self.docks.values() {
+            x.emit(msg.clone());
+        }
+    }
+
+    /// Creates a bar and dock for every new monitor and drops those whose monitor is gone.
+    fn sync_monitors(&mut self, sender: &ComponentSender<Self>) {
+        let monitors = monitors();
+        let dock_monitors = match self.config.dock.all_outputs {
+            true => &monitors[..],
+            false => &monitors[..monitors.len().min(1)],
+        };
+        self.bars
+            .retain(|name, _| monitors.iter().any(|(x, _)| x == name));
+        self.docks
+            .retain(|name, _| dock_monitors.iter().any(|(x, _)| x == name));
+
+        for (name, monitor) in monitors.iter() {
+            if self.bars.contains_key(name) {
+                continue;
+            }
+
+            let builder = bar::BarModel::builder();
+            relm4::main_application().add_window(&builder.root);
+            let bar = builder
+                .launch(bar::Init {
+                    config: self.config.bar.clone(),
+                    overrides: self.config.dock.overrides.clone(),
+                    monitor: Some(monitor.clone()),
+                })
+                .forward(sender.input_sender(), |msg| match msg {
+                    bar::Output::ToggleControlPanel => Input::ToggleControlPanel,
+                    bar::Output::ArbitrarySwayMsg(x) => Input::ArbitrarySwayMsg(x),
+                    bar::Output::ToggleMute => Input::ToggleMute,
+                    bar::Output::StepVolume(x) => Input::StepVolume(x),
+                    bar::Output::StepBrightness(x) => Input::StepBrightness(x),
+                    bar::Output::ShowControlPanel(x) => Input::ShowControlPanel(x),
+                    bar::Output::ToggleMicrophoneMute => Input::ToggleMicrophoneMute,
+                });
+
+            // catch up on whatever the workers already reported
+            bar.emit(bar::Input::UpdateVolume(Volume {
+                level: self.state.volume,
+                muted: self.state.muted,
+            }));
+            bar.emit(bar::Input::UpdateMicrophone(self.microphone));
+            bar.emit(bar::Input::UpdateRecording(self.recording));
+            bar.emit(bar::Input::UpdateBrightness(self.state.brightness));
+            bar.emit(bar::Input::UpdateBattery(self.battery.clone()));
+            bar.emit(bar::Input::UpdateWorkspaces(self.workspaces.clone()));
+            self.bars.insert(name.clone(), bar);
+        }
+
+        for (name, monitor) in dock_monitors.iter() {
+            if self.docks.contains_key(name) {
+                continue;
+            }
+
+            let builder = dock::DockModel::builder();
+            relm4::main_application().add_window(&builder.root);
+            let dock = builder
+                .launch((self.config.dock.clone(), Some(monitor.clone())))
+                .forward(sender.input_sender(), |msg| match msg {
+                    dock::Output::Focus(x) => Input::FocusWindow(x),
+                    dock::Output::Launch(x) => Input::ArbitrarySwayMsg(x),
+                });
+
+            if !self.state.dock_enabled {
+                dock.emit(dock::Input::Toggle);
+            }
+            self.docks.insert(name.clone(), dock);
+        }
+    }
+
+    /// Turns swaync's do not disturb mode off if it's on, or on if it's off.
+    fn toggle_notifs(&mut self) -> Result<(), String> {
+        let output = Command::new("swaync-client")
+            .arg(if self.state.notifications {
+                "-dn"
+            } else {
+                "-df"
+            })
+            .output()
+            .map_err(|e| format!("Failed to toggle notifications: {e}"))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "Failed to toggle notifications: swaync-client {}: {}",
+                output.status,
+                stderr.trim()
+            ));
+        }
+
+        self.state.notifications = !self.state.notifications;
+        self.control_panel
+            .emit(control_panel::Input::UpdateNotifs(self.state.notifications));
+        Ok(())
+    }
+
+    fn handle_request(
+        &self,
+        request: ipc::Request,
+        sender: &ComponentSender<Self>,
+    ) -> ipc::Response {
+        let input = match request {
+            ipc::Request::Query => return ipc::Response::State(self.state.clone()),
+            ipc::Request::ToggleDock => Input::ToggleDock,
+            ipc::Request::ToggleControlPanel => Input::ToggleControlPanel,
+            ipc::Request::ToggleTiling => Input::ToggleTiling(!self.state.tiling),
+            ipc::Request::SetTiling { tiling } => Input::ToggleTiling(tiling),
+            ipc::Request::ToggleNotifs => Input::ToggleNotifs,
+            ipc::Request::SetVolume { volume } => {
+                let max = self.config.workers.audio.max_volume;
+                if !(0.0..=max).contains(&volume) {
+                    return ipc::Response::error(format!(
+                        "Volume must be between 0 and {max}, got {volume}."
+                    ));
+                }
+                Input::SetVolume(volume)
+            }
+            ipc::Request::ToggleMute => Input::ToggleMute,
+            ipc::Request::SetMute { muted } => {
+                if muted == self.state.muted {
+                    return ipc::Response::Ok;
+                }
+                Input::ToggleMute
+            }
+            ipc::Request::SetBrightness { brightness, .. } => {
+                if brightness > 100 {
+                    return ipc::Response::error(format!(
+                        "Brightness must be between 0 and 100, got {brightness}."
+                    ));
+                }
+                Input::SetBrightness(brightness)
+            }
+            ipc::Request::ReloadCss => Input::ReloadCSS,
+        };
+
+        sender.input(input);
+        ipc::Response::Ok
+    }
+}
+
 fn main() {
     let css = include_str!("../resources/bar.css");
 
@@ -223,12 +609,24 @@ fn main() {
     let app = RelmApp::new("org.poach3r.swirly");
 
     set_global_css(css);
-    app.run::<AppModel>(());
+    app.run::<AppModel>(config::load());
 }
 
-fn get_battery() -> Result<starship_battery::Battery, starship_battery::Error> {
-    Ok(starship_battery::Manager::new()?
-        .batteries()?
-        .last()
-        .unwrap()?)
+/// Lists the connected monitors by connector name, which sway uses as the output name.
+fn monitors() -> Vec<(String, gdk::Monitor)> {
+    let Some(display) = gdk::Display::default() else {
+        return Vec::new();
+    };
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i).and_downcast::<gdk::Monitor>())
+        .filter_map(|x| match x.connector() {
+            Some(name) => Some((name.to_string(), x)),
+            // there'd be no telling which sway output's workspaces it should show
+            None => {
+                log::warn!("Skipping a monitor without a connector name.");
+                None
+            }
+        })
+        .collect()
 }
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use gtk::prelude::*;
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::prelude::*;
+
+use crate::config::OsdConfig;
+use crate::workers::audio_worker::Volume;
+
+/// How long the popup takes to fade out, which should match the transition in the CSS.
+const FADE_DURATION: Duration = Duration::from_millis(300);
+
+/// A popup showing volume and brightness changes, wherever they come from.
+#[tracker::track]
+pub struct OsdModel {
+    visible: bool,
+    fading: bool,
+    icon: &'static str,
+    /// Fraction of the normal level, capped at one.
+    fraction: f64,
+    label: String,
+    #[tracker::do_not_track]
+    config: OsdConfig,
+    // the last values reported, to tell changes apart from the startup sync and repeats
+    #[tracker::do_not_track]
+    volume: Option<Volume>,
+    #[tracker::do_not_track]
+    brightness: Option<u32>,
+    /// Counts how often the popup was shown, so timers from earlier changes don't hide it early.
+    #[tracker::do_not_track]
+    shown: u64,
+}
+
+#[derive(Debug)]
+pub enum Input {
+    UpdateVolume(Volume),
+    UpdateBrightness(u32),
+    UpdateConfig(OsdConfig),
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Fade(u64),
+    Hide(u64),
+}
+
+#[relm4::component(pub)]
+impl Component for OsdModel {
+    type Init = OsdConfig;
+    type Input = Input;
+    type Output = ();
+    type CommandOutput = Command;
+
+    view! {
+        #[name = "window"]
+        gtk::Window {
+            #[track = "model.changed_visible()"]
+            set_visible: model.visible,
+            set_can_target: false,
+
+            gtk::Box {
+                add_css_class: "osd",
+                #[track = "model.changed_fading()"]
+                set_class_active: ("fading", model.fading),
+                set_spacing: 12,
+
+                gtk::Image {
+                    #[track = "model.changed_icon()"]
+                    set_icon_name: Some(model.icon),
+                },
+
+                gtk::ProgressBar {
+                    set_valign: gtk::Align::Center,
+                    set_hexpand: true,
+                    #[track = "model.changed_fraction()"]
+                    set_fraction: model.fraction,
+                },
+
+                gtk::Label {
+                    set_width_chars: 5,
+                    set_xalign: 1.0,
+                    #[track = "model.changed_label()"]
+                    set_label: &model.label,
+                },
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = OsdModel {
+            visible: false,
+            fading: false,
+            icon: "audio-volume-high-symbolic",
+            fraction: 0.0,
+            label: String::new(),
+            config,
+            volume: None,
+            brightness: None,
+            shown: 0,
+            tracker: 0,
+        };
+        let widgets = view_output!();
+
+        widgets.window.init_layer_shell();
+        widgets.window.set_layer(Layer::Overlay);
+        widgets.window.set_anchor(Edge::Bottom, true);
+        widgets.window.set_margin(Edge::Bottom, 96);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        self.reset();
+
+        match msg {
+            Input::UpdateVolume(x) => {
+                // the first value is the state swirly started in, not a change
+                if self.volume.replace(x).is_none_or(|y| y == x) {
+                    return;
+                }
+                self.show(
+                    match x.level {
+                        _ if x.muted => "audio-volume-muted-symbolic",
+                        ..=0f64 => "audio-volume-muted-symbolic",
+                        ..30f64 => "audio-volume-low-symbolic",
+                        ..70f64 => "audio-volume-medium-symbolic",
+                        ..=100f64 => "audio-volume-high-symbolic",
+                        _ => "audio-volume-overamplified-symbolic",
+                    },
+                    x.level,
+                    match x.muted {
+                        true => String::from("Muted"),
+                        false => format!("{}%", x.level.round()),
+                    },
+                    &sender,
+                );
+            }
+            Input::UpdateBrightness(x) => {
+                if self.brightness.replace(x).is_none_or(|y| y == x) {
+                    return;
+                }
+                self.show(
+                    match x {
+                        0..33 => "display-brightness-low-symbolic",
+                        33..66 => "display-brightness-medium-symbolic",
+                        _ => "display-brightness-high-symbolic",
+                    },
+                    x as f64,
+                    format!("{x}%"),
+                    &sender,
+                );
+            }
+            Input::UpdateConfig(x) => {
+                self.config = x;
+                if !self.config.enabled {
+                    self.set_visible(false);
+                }
+            }
+        }
+    }
+
+    fn update_cmd(
+        &mut self,
+        msg: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        self.reset();
+
+        match msg {
+            Command::Fade(x) if x == self.shown => {
+                self.set_fading(true);
+                sender.oneshot_command(async move {
+                    tokio::time::sleep(FADE_DURATION).await;
+                    Command::Hide(x)
+                });
+            }
+            Command::Hide(x) if x == self.shown => {
+                self.set_visible(false);
+            }
+            // a later change is being shown
+            _ => (),
+        }
+    }
+}
+
+impl OsdModel {
+    /// Shows `level`, a percentage, until the configured timeout passes without another change.
+    fn show(
+        &mut self,
+        icon: &'static str,
+        level: f64,
+        label: String,
+        sender: &ComponentSender<Self>,
+    ) {
+        if !self.config.enabled {
+            return;
+        }
+
+        self.set_icon(icon);
+        self.set_fraction((level / 100.0).clamp(0.0, 1.0));
+        self.set_label(label);
+        self.set_fading(false);
+        self.set_visible(true);
+
+        self.shown += 1;
+        let shown = self.shown;
+        let timeout = Duration::from_millis(self.config.timeout);
+        sender.oneshot_command(async move {
+            tokio::time::sleep(timeout).await;
+            Command::Fade(shown)
+        });
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use gtk::{gdk::Display, CssProvider};
+
+use crate::config;
+
+pub const FILE_NAME: &str = "style.css";
+
+/// The user's stylesheet, layered over the built-in one.
+pub struct UserStyle {
+    provider: CssProvider,
+    loaded: bool,
+}
+
+impl UserStyle {
+    pub fn new() -> Self {
+        let provider = CssProvider::new();
+        provider.connect_parsing_error(|_, section, e| {
+            log::error!("Failed to parse CSS at {}: {e}", section.to_str());
+        });
+
+        let mut style = Self {
+            provider,
+            loaded: false,
+        };
+        style.reload();
+        style
+    }
+
+    /// Reads `style.css` from the config directory again,
+    /// removing the user's styling if the file no longer exists.
+    pub fn reload(&mut self) {
+        let Some(display) = Display::default() else {
+            log::error!("Failed to find display for CSS.");
+            return;
+        };
+
+        match path() {
+            Some(path) if path.exists() => {
+                self.provider.load_from_path(&path);
+                if !self.loaded {
+                    gtk::style_context_add_provider_for_display(
+                        &display,
+                        &self.provider,
+                        gtk::STYLE_PROVIDER_PRIORITY_USER,
+                    );
+                    self.loaded = true;
+                }
+                log::info!("Loaded {}.", path.display());
+            }
+            _ => {
+                if self.loaded {
+                    gtk::style_context_remove_provider_for_display(&display, &self.provider);
+                    self.loaded = false;
+                }
+            }
+        }
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    config::dir().map(|x| x.join(FILE_NAME))
+}
@@ -1,163 +1,314 @@
-mod poller;
+mod pipewire;
+mod pulseaudio;
 mod utils;
 
-use libpulse_binding::context::Context;
-use libpulse_binding::mainloop::standard::Mainloop;
-use relm4::{Component, ComponentSender, Worker, WorkerController};
-use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use relm4::{ComponentSender, Worker};
+
+use crate::config::{AudioBackend, AudioConfig};
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// How long backends wait before reconnecting to a sound server that went away,
+/// doubling up to [`MAX_RETRY_DELAY`] while it stays away.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A sound server the worker can control.
+///
+/// Levels are percentages of the normal volume, the same scale [`Volume`] uses.
+pub trait Backend: Send {
+    /// Returns the volume of the default device.
+    fn volume(&mut self, kind: Kind) -> Result<Volume, Error>;
+    fn set_volume(&mut self, kind: Kind, level: f64) -> Result<(), Error>;
+    fn set_mute(&mut self, kind: Kind, muted: bool) -> Result<(), Error>;
+    /// Returns every device and the name of the default one.
+    fn devices(&mut self, kind: Kind) -> Result<(Vec<Device>, String), Error>;
+    fn set_default(&mut self, kind: Kind, name: &str) -> Result<(), Error>;
+    /// Moves every playing or recording stream onto the device called `name`.
+    fn move_streams(&mut self, kind: Kind, name: &str) -> Result<(), Error>;
+    /// Returns every playing stream.
+    fn streams(&mut self) -> Result<Vec<Stream>, Error>;
+    fn set_stream_volume(&mut self, index: u32, level: f64) -> Result<(), Error>;
+    fn set_stream_mute(&mut self, index: u32, muted: bool) -> Result<(), Error>;
+    /// Returns whether any application is recording.
+    fn recording(&mut self) -> Result<bool, Error>;
+}
 
 pub struct AsyncHandler {
-    poller: WorkerController<poller::AsyncHandler>,
-    main_loop: MyMainLoop,
-    context: MyContext,
+    backend: Option<Box<dyn Backend>>,
+    move_streams: bool,
+    max_volume: f64,
+    // the last values reported, so unrelated events don't cause updates
+    volume: Option<Volume>,
+    microphone: Option<Volume>,
+    sinks: Option<(Vec<Device>, String)>,
+    sources: Option<(Vec<Device>, String)>,
+    recording: Option<bool>,
+    streams: Option<Vec<Stream>>,
 }
 
-struct MyMainLoop {
-    val: Arc<Mutex<Option<Mainloop>>>,
+/// Whether something is for speakers or a microphone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Output,
+    Input,
 }
 
-struct MyContext {
-    val: Arc<Mutex<Option<Context>>>,
+/// The volume of a sink or source.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Volume {
+    /// Percentage of the normal volume, which may go above 100.
+    pub level: f64,
+    pub muted: bool,
 }
 
-unsafe impl Send for MyMainLoop {}
-unsafe impl Send for MyContext {}
+/// An output or input device.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Device {
+    pub name: String,
+    pub description: String,
+    /// Descriptions of the device's ports, such as speakers and headphones.
+    pub ports: Vec<String>,
+    pub active_port: Option<String>,
+}
+
+/// An application playing audio.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Stream {
+    pub index: u32,
+    pub name: String,
+    /// The icon the application asks for, or failing that its binary name.
+    pub icon: String,
+    pub volume: Volume,
+}
 
 #[derive(Debug)]
 pub enum Input {
     SetVolume(f64),
-    GetVolume,
+    ToggleMute,
+    SetDefaultSink(String),
+    SetMicrophoneVolume(f64),
+    ToggleMicrophoneMute,
+    SetDefaultSource(String),
+    SetStreamVolume(u32, f64),
+    SetStreamMute(u32, bool),
+    Refresh,
+    /// Only the backend is fixed at startup.
+    UpdateConfig(AudioConfig),
 }
 
 #[derive(Debug)]
 pub enum Output {
-    UpdateVolume(f64),
+    UpdateVolume(Volume),
+    /// All sinks and the name of the default one.
+    UpdateSinks(Vec<Device>, String),
+    UpdateMicrophone(Volume),
+    /// All sources and the name of the default one.
+    UpdateSources(Vec<Device>, String),
+    /// Whether any application is recording.
+    UpdateRecording(bool),
+    UpdateStreams(Vec<Stream>),
 }
 
 impl Worker for AsyncHandler {
-    type Init = ();
+    type Init = AudioConfig;
     type Input = Input;
     type Output = Output;
 
-    fn init(_init: Self::Init, sender: ComponentSender<Self>) -> Self {
-        let mut main_loop = match Mainloop::new()
-            .ok_or_else(|| log::error!("Failed to initialize PulseAudio main loop."))
-        {
-            Ok(x) => Some(x),
-            Err(_) => None,
-        };
-        let context = if let Some(x) = main_loop.as_mut() {
-            if let Ok(y) = utils::connect(x) {
-                Some(y)
-            } else {
-                log::error!("Failed to connect to PulseAudio.");
-                None
-            }
-        } else {
-            None
-        };
-        let poller = poller::AsyncHandler::builder().detach_worker(()).forward(
-            sender.input_sender(),
-            |msg| match msg {
-                poller::Output::Poll => Input::GetVolume,
-            },
-        );
+    fn init(config: Self::Init, sender: ComponentSender<Self>) -> Self {
+        let backend = connect(config.backend, sender.input_sender());
 
-        poller.emit(poller::Input::Start);
+        sender.input(Input::Refresh);
 
         Self {
-            poller,
-            main_loop: MyMainLoop {
-                val: Arc::new(Mutex::new(main_loop)),
-            },
-            context: MyContext {
-                val: Arc::new(Mutex::new(context)),
-            },
+            backend,
+            move_streams: config.move_streams,
+            max_volume: config.max_volume,
+            volume: None,
+            microphone: None,
+            sinks: None,
+            sources: None,
+            recording: None,
+            streams: None,
         }
     }
 
     fn update(&mut self, msg: Input, sender: ComponentSender<Self>) {
+        // the reason there's no backend was logged when connecting
+        let Some(backend) = self.backend.as_mut() else {
+            return;
+        };
+
         match msg {
             Input::SetVolume(x) => {
-                let mut context_guard = if let Ok(x) = self.context.val.lock() {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio context.");
+                if let Err(e) = backend.set_volume(Kind::Output, x.clamp(0.0, self.max_volume)) {
+                    log::error!("Failed to set output volume: {e}");
+                }
+            }
+            Input::ToggleMute => match backend.volume(Kind::Output) {
+                Ok(x) => {
+                    if let Err(e) = backend.set_mute(Kind::Output, !x.muted) {
+                        log::error!("Failed to set output mute: {e}");
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Failed to get output volume: {e}");
+                }
+            },
+            Input::SetDefaultSink(x) => {
+                if let Err(e) = backend.set_default(Kind::Output, &x) {
+                    log::error!("Failed to set default sink to {x}: {e}");
                     return;
-                };
-
-                let context = if let Some(x) = &mut *context_guard {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio context.");
+                }
+                if self.move_streams {
+                    if let Err(e) = backend.move_streams(Kind::Output, &x) {
+                        log::error!("Failed to move streams to {x}: {e}");
+                    }
+                }
+            }
+            Input::SetMicrophoneVolume(x) => {
+                if let Err(e) = backend.set_volume(Kind::Input, x.clamp(0.0, self.max_volume)) {
+                    log::error!("Failed to set input volume: {e}");
+                }
+            }
+            Input::ToggleMicrophoneMute => match backend.volume(Kind::Input) {
+                Ok(x) => {
+                    if let Err(e) = backend.set_mute(Kind::Input, !x.muted) {
+                        log::error!("Failed to set input mute: {e}");
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Failed to get input volume: {e}");
+                }
+            },
+            Input::SetDefaultSource(x) => {
+                if let Err(e) = backend.set_default(Kind::Input, &x) {
+                    log::error!("Failed to set default source to {x}: {e}");
                     return;
-                };
+                }
+                if self.move_streams {
+                    if let Err(e) = backend.move_streams(Kind::Input, &x) {
+                        log::error!("Failed to move recordings to {x}: {e}");
+                    }
+                }
+            }
+            Input::SetStreamVolume(index, x) => {
+                if let Err(e) = backend.set_stream_volume(index, x.clamp(0.0, self.max_volume)) {
+                    log::error!("Failed to set stream volume: {e}");
+                }
+            }
+            Input::SetStreamMute(index, x) => {
+                if let Err(e) = backend.set_stream_mute(index, x) {
+                    log::error!("Failed to set stream mute: {e}");
+                }
+            }
+            Input::UpdateConfig(x) => {
+                self.move_streams = x.move_streams;
+                self.max_volume = x.max_volume;
+            }
+            Input::Refresh => {
+                match backend.volume(Kind::Output) {
+                    Ok(x) => {
+                        if self.volume != Some(x) {
+                            self.volume = Some(x);
+                            sender.output(Output::UpdateVolume(x)).unwrap();
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to get output volume: {e}");
+                    }
+                }
 
-                let mut main_loop_guard = if let Ok(x) = self.main_loop.val.lock() {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio Mainloop.");
-                    return;
-                };
+                // there might not be a microphone at all
+                if let Ok(x) = backend.volume(Kind::Input) {
+                    if self.microphone != Some(x) {
+                        self.microphone = Some(x);
+                        sender.output(Output::UpdateMicrophone(x)).unwrap();
+                    }
+                }
 
-                let main_loop = if let Some(x) = &mut *main_loop_guard {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio Mainloop.");
-                    return;
-                };
-
-                if let Ok(volumes) = utils::get_output_volumes(main_loop, context).as_mut() {
-                    utils::map_volumes(volumes, |_| x);
-                    match utils::set_output_volumes(main_loop, context, volumes) {
-                        Ok(_) => (),
-                        Err(e) => {
-                            log::error!("Failed to set output volume: {e}");
+                match backend.devices(Kind::Output) {
+                    Ok(x) => {
+                        if self.sinks.as_ref() != Some(&x) {
+                            self.sinks = Some(x.clone());
+                            sender.output(Output::UpdateSinks(x.0, x.1)).unwrap();
                         }
                     }
-                } else {
-                    log::warn!("Failed to get outputs from PulseAudio.");
+                    Err(e) => {
+                        log::warn!("Failed to get sinks: {e}");
+                    }
                 }
-            }
-            Input::GetVolume => {
-                let mut context_guard = if let Ok(x) = self.context.val.lock() {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio context.");
-                    return;
-                };
-
-                let context = if let Some(x) = &mut *context_guard {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio context.");
-                    return;
-                };
 
-                let mut main_loop_guard = if let Ok(x) = self.main_loop.val.lock() {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio Mainloop.");
-                    return;
-                };
+                match backend.devices(Kind::Input) {
+                    Ok(x) => {
+                        if self.sources.as_ref() != Some(&x) {
+                            self.sources = Some(x.clone());
+                            sender.output(Output::UpdateSources(x.0, x.1)).unwrap();
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to get sources: {e}");
+                    }
+                }
 
-                let main_loop = if let Some(x) = &mut *main_loop_guard {
-                    x
-                } else {
-                    log::warn!("Failed to get PulseAudio Mainloop.");
-                    return;
-                };
+                match backend.streams() {
+                    Ok(x) => {
+                        if self.streams.as_ref() != Some(&x) {
+                            self.streams = Some(x.clone());
+                            sender.output(Output::UpdateStreams(x)).unwrap();
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to get streams: {e}");
+                    }
+                }
 
-                if let Ok(volumes) = utils::get_output_volumes(main_loop, context) {
-                    sender
-                        .output(Output::UpdateVolume(utils::volume_to_percentage(
-                            volumes.get()[0],
-                        )))
-                        .unwrap();
-                } else {
-                    log::warn!("Failed to get outputs from PulseAudio.");
+                match backend.recording() {
+                    Ok(x) => {
+                        if self.recording != Some(x) {
+                            self.recording = Some(x);
+                            sender.output(Output::UpdateRecording(x)).unwrap();
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to get recordings: {e}");
+                    }
                 }
             }
         }
     }
 }
+
+/// Connects to the configured sound server, preferring PipeWire when either would do.
+///
+/// Backends send [`Input::Refresh`] to `changed` whenever something on the server changes.
+fn connect(backend: AudioBackend, changed: &relm4::Sender<Input>) -> Option<Box<dyn Backend>> {
+    if matches!(backend, AudioBackend::Auto | AudioBackend::PipeWire) {
+        match pipewire::PipeWire::new(changed) {
+            Ok(x) => {
+                log::info!("Using PipeWire for audio.");
+                return Some(Box::new(x));
+            }
+            Err(e) if backend == AudioBackend::PipeWire => {
+                log::error!("Failed to connect to PipeWire: {e}");
+                return None;
+            }
+            Err(e) => {
+                log::info!("Failed to connect to PipeWire, trying PulseAudio: {e}");
+            }
+        }
+    }
+
+    match pulseaudio::PulseAudio::new(changed) {
+        Ok(x) => {
+            log::info!("Using PulseAudio for audio.");
+            Some(Box::new(x))
+        }
+        Err(e) => {
+            log::error!("Failed to connect to PulseAudio: {e}");
+            None
+        }
+    }
+}
@@ -0,0 +1,842 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::rc::{Rc, Weak};
+use std::sync::{mpsc, Arc, Mutex};
+
+use ::pipewire as pw;
+use pw::device::DeviceListener;
+use pw::metadata::{Metadata, MetadataListener};
+use pw::node::{Node, NodeListener};
+use pw::registry::{GlobalObject, Registry};
+use pw::spa::param::ParamType;
+use pw::spa::pod::deserialize::PodDeserializer;
+use pw::spa::pod::serialize::PodSerializer;
+use pw::spa::pod::{Object, Pod, Property, PropertyFlags, Value, ValueArray};
+use pw::spa::sys;
+use pw::spa::utils::dict::DictRef;
+use pw::types::ObjectType;
+
+use super::{Backend, Device, Error, Input, Kind, Stream, Volume, MAX_RETRY_DELAY, RETRY_DELAY};
+
+/// A sound server spoken to natively through libpipewire.
+///
+/// PipeWire's objects can't leave the thread that made them, so a thread of its own
+/// keeps a copy of the audio graph for the worker to read and takes commands over a channel.
+pub struct PipeWire {
+    graph: Arc<Mutex<Graph>>,
+    commands: pw::channel::Sender<Command>,
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: BTreeMap<u32, Entry>,
+    /// Sound cards by the id of their device object.
+    cards: BTreeMap<u32, Card>,
+    default_sink: String,
+    default_source: String,
+}
+
+impl Graph {
+    /// Returns the descriptions of a node's routes and the one in use,
+    /// which is what PulseAudio calls ports.
+    fn ports(&self, entry: &Entry) -> (Vec<String>, Option<String>) {
+        let Some((card, device)) = entry.card else {
+            return (Vec::new(), None);
+        };
+        let Some(card) = self.cards.get(&card) else {
+            return (Vec::new(), None);
+        };
+        let direction = match entry.class {
+            Class::Sink | Class::Playback => sys::SPA_DIRECTION_OUTPUT,
+            Class::Source | Class::Recording => sys::SPA_DIRECTION_INPUT,
+        };
+        let ports = card
+            .routes
+            .values()
+            .filter(|x| x.direction == direction && x.devices.contains(&device))
+            .map(|x| x.description.clone())
+            .collect();
+        let active = card.active.get(&device).map(|x| x.description.clone());
+        (ports, active)
+    }
+}
+
+struct Entry {
+    class: Class,
+    /// What other objects refer to the node by when picking a target.
+    serial: Option<String>,
+    name: String,
+    description: String,
+    icon: String,
+    volume: Volume,
+    channels: usize,
+    /// The card the node belongs to and which of the card's devices it is.
+    card: Option<(u32, i32)>,
+}
+
+#[derive(Default)]
+struct Card {
+    /// Every route the card has, by index.
+    routes: BTreeMap<i32, Route>,
+    /// The route each of the card's devices is using, by device.
+    active: BTreeMap<i32, Route>,
+}
+
+/// A way sound leaves or enters a card, like its speakers or a headset's microphone.
+#[derive(Debug, Clone, Default)]
+struct Route {
+    index: i32,
+    direction: u32,
+    description: String,
+    /// The card's devices that can use the route.
+    devices: Vec<i32>,
+    /// The device using the route, only known for active routes.
+    device: Option<i32>,
+    channels: usize,
+}
+
+/// Where a node's volume is set.
+enum Target {
+    /// The route its card is using for it, which is where PulseAudio and wpctl set it too,
+    /// so the session manager saves it.
+    Route {
+        card: u32,
+        index: i32,
+        device: i32,
+        channels: usize,
+    },
+    /// The node itself, for streams and nodes that aren't part of a card.
+    Node { channels: usize },
+}
+
+impl Target {
+    fn channels(&self) -> usize {
+        match self {
+            Self::Route { channels, .. } | Self::Node { channels } => *channels,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Sink,
+    Source,
+    Playback,
+    Recording,
+}
+
+impl Class {
+    fn parse(media_class: &str) -> Option<Self> {
+        match media_class {
+            "Audio/Sink" => Some(Self::Sink),
+            "Audio/Source" | "Audio/Source/Virtual" => Some(Self::Source),
+            "Stream/Output/Audio" => Some(Self::Playback),
+            "Stream/Input/Audio" => Some(Self::Recording),
+            _ => None,
+        }
+    }
+
+    fn device(kind: Kind) -> Self {
+        match kind {
+            Kind::Output => Self::Sink,
+            Kind::Input => Self::Source,
+        }
+    }
+
+    fn stream(kind: Kind) -> Self {
+        match kind {
+            Kind::Output => Self::Playback,
+            Kind::Input => Self::Recording,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    SetVolume(u32, f64),
+    SetMute(u32, bool),
+    SetDefault(Kind, String),
+    /// Moves a stream onto the node with the given serial.
+    Move(u32, String),
+}
+
+impl PipeWire {
+    pub fn new(changed: &relm4::Sender<Input>) -> Result<Self, Error> {
+        let graph = Arc::new(Mutex::new(Graph::default()));
+        let (commands, receiver) = pw::channel::channel();
+        let (ready, connected) = mpsc::channel();
+
+        std::thread::spawn({
+            let graph = graph.clone();
+            let changed = changed.clone();
+            move || run(graph, receiver, changed, ready)
+        });
+        connected.recv()??;
+
+        Ok(Self { graph, commands })
+    }
+
+    fn send(&self, command: Command) -> Result<(), Error> {
+        self.commands
+            .send(command)
+            .map_err(|_| Error::from("the PipeWire thread has stopped"))
+    }
+
+    /// Returns the id of the default device.
+    fn default(&self, kind: Kind) -> Result<u32, Error> {
+        let graph = self.graph.lock().unwrap();
+        let name = match kind {
+            Kind::Output => &graph.default_sink,
+            Kind::Input => &graph.default_source,
+        };
+        graph
+            .nodes
+            .iter()
+            .find(|(_, x)| x.class == Class::device(kind) && &x.name == name)
+            .map(|(id, _)| *id)
+            .ok_or_else(|| Error::from("there is no default device"))
+    }
+}
+
+impl Backend for PipeWire {
+    fn volume(&mut self, kind: Kind) -> Result<Volume, Error> {
+        let id = self.default(kind)?;
+        Ok(self.graph.lock().unwrap().nodes[&id].volume)
+    }
+
+    fn set_volume(&mut self, kind: Kind, level: f64) -> Result<(), Error> {
+        self.send(Command::SetVolume(self.default(kind)?, level))
+    }
+
+    fn set_mute(&mut self, kind: Kind, muted: bool) -> Result<(), Error> {
+        self.send(Command::SetMute(self.default(kind)?, muted))
+    }
+
+    fn devices(&mut self, kind: Kind) -> Result<(Vec<Device>, String), Error> {
+        let graph = self.graph.lock().unwrap();
+        let devices = graph
+            .nodes
+            .values()
+            .filter(|x| x.class == Class::device(kind))
+            .map(|x| {
+                let (ports, active_port) = graph.ports(x);
+                Device {
+                    name: x.name.clone(),
+                    description: x.description.clone(),
+                    ports,
+                    active_port,
+                }
+            })
+            .collect();
+        let default = match kind {
+            Kind::Output => graph.default_sink.clone(),
+            Kind::Input => graph.default_source.clone(),
+        };
+        Ok((devices, default))
+    }
+
+    fn set_default(&mut self, kind: Kind, name: &str) -> Result<(), Error> {
+        self.send(Command::SetDefault(kind, name.to_owned()))
+    }
+
+    fn move_streams(&mut self, kind: Kind, name: &str) -> Result<(), Error> {
+        let graph = self.graph.lock().unwrap();
+        let serial = graph
+            .nodes
+            .values()
+            .find(|x| x.class == Class::device(kind) && x.name == name)
+            .and_then(|x| x.serial.clone())
+            .ok_or_else(|| Error::from(format!("there is no device called {name}")))?;
+        for (id, _) in graph
+            .nodes
+            .iter()
+            .filter(|(_, x)| x.class == Class::stream(kind))
+        {
+            self.send(Command::Move(*id, serial.clone()))?;
+        }
+        Ok(())
+    }
+
+    fn streams(&mut self) -> Result<Vec<Stream>, Error> {
+        Ok(self
+            .graph
+            .lock()
+            .unwrap()
+            .nodes
+            .iter()
+            .filter(|(_, x)| x.class == Class::Playback)
+            .map(|(id, x)| Stream {
+                index: *id,
+                name: x.description.clone(),
+                icon: x.icon.clone(),
+                volume: x.volume,
+            })
+            .collect())
+    }
+
+    fn set_stream_volume(&mut self, index: u32, level: f64) -> Result<(), Error> {
+        self.send(Command::SetVolume(index, level))
+    }
+
+    fn set_stream_mute(&mut self, index: u32, muted: bool) -> Result<(), Error> {
+        self.send(Command::SetMute(index, muted))
+    }
+
+    fn recording(&mut self) -> Result<bool, Error> {
+        Ok(self
+            .graph
+            .lock()
+            .unwrap()
+            .nodes
+            .values()
+            .any(|x| x.class == Class::Recording))
+    }
+}
+
+/// Everything the PipeWire thread's callbacks share.
+struct State {
+    graph: Arc<Mutex<Graph>>,
+    changed: relm4::Sender<Input>,
+    registry: Weak<Registry>,
+    nodes: RefCell<BTreeMap<u32, (Node, NodeListener)>>,
+    devices: RefCell<BTreeMap<u32, (pw::device::Device, DeviceListener)>>,
+    metadata: RefCell<Option<(Metadata, MetadataListener)>>,
+}
+
+impl State {
+    /// Changes the graph and tells the worker about it.
+    fn update(&self, change: impl FnOnce(&mut Graph)) {
+        change(&mut self.graph.lock().unwrap());
+        self.changed.emit(Input::Refresh);
+    }
+
+    fn add(self: &Rc<Self>, global: &GlobalObject<&DictRef>) {
+        let Some(registry) = self.registry.upgrade() else {
+            return;
+        };
+        let Some(props) = global.props else {
+            return;
+        };
+
+        match global.type_ {
+            ObjectType::Node => {
+                let Some(class) = props.get("media.class").and_then(Class::parse) else {
+                    return;
+                };
+                let node: Node = match registry.bind(global) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        log::warn!("Failed to bind PipeWire node {}: {e}", global.id);
+                        return;
+                    }
+                };
+
+                let id = global.id;
+                let listener = node
+                    .add_listener_local()
+                    .info({
+                        let state = Rc::downgrade(self);
+                        move |info| {
+                            if let (Some(state), Some(props)) = (state.upgrade(), info.props()) {
+                                state.update(|x| {
+                                    if let Some(entry) = x.nodes.get_mut(&id) {
+                                        entry.describe(props);
+                                    }
+                                });
+                            }
+                        }
+                    })
+                    .param({
+                        let state = Rc::downgrade(self);
+                        move |_, _, _, _, param| {
+                            let (Some(state), Some(param)) = (state.upgrade(), param) else {
+                                return;
+                            };
+                            let (volumes, muted) = parse_props(param);
+                            state.update(|x| {
+                                if let Some(entry) = x.nodes.get_mut(&id) {
+                                    if let Some(volumes) = volumes {
+                                        entry.channels = volumes.len();
+                                        entry.volume.level = linear_to_percentage(&volumes);
+                                    }
+                                    if let Some(muted) = muted {
+                                        entry.volume.muted = muted;
+                                    }
+                                }
+                            });
+                        }
+                    })
+                    .register();
+                node.subscribe_params(&[ParamType::Props]);
+
+                let mut entry = Entry {
+                    class,
+                    serial: props.get("object.serial").map(String::from),
+                    name: String::new(),
+                    description: String::new(),
+                    icon: String::new(),
+                    volume: Volume::default(),
+                    channels: 0,
+                    card: None,
+                };
+                entry.describe(props);
+                self.update(|x| {
+                    x.nodes.insert(id, entry);
+                });
+                self.nodes.borrow_mut().insert(id, (node, listener));
+            }
+            // sound cards, whose routes hold their nodes' volumes
+            ObjectType::Device if props.get("media.class") == Some("Audio/Device") => {
+                let device: pw::device::Device = match registry.bind(global) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        log::warn!("Failed to bind PipeWire device {}: {e}", global.id);
+                        return;
+                    }
+                };
+
+                let id = global.id;
+                let listener = device
+                    .add_listener_local()
+                    .param({
+                        let state = Rc::downgrade(self);
+                        move |_, kind, _, _, param| {
+                            let (Some(state), Some(route)) =
+                                (state.upgrade(), param.and_then(parse_route))
+                            else {
+                                return;
+                            };
+                            state.update(|x| {
+                                let card = x.cards.entry(id).or_default();
+                                if kind == ParamType::EnumRoute {
+                                    card.routes.insert(route.index, route);
+                                } else if let Some(device) = route.device {
+                                    card.active.insert(device, route);
+                                }
+                            });
+                        }
+                    })
+                    .register();
+                device.subscribe_params(&[ParamType::EnumRoute, ParamType::Route]);
+
+                self.update(|x| {
+                    x.cards.insert(id, Card::default());
+                });
+                self.devices.borrow_mut().insert(id, (device, listener));
+            }
+            // the metadata called "default" holds the default devices
+            ObjectType::Metadata if props.get("metadata.name") == Some("default") => {
+                let metadata: Metadata = match registry.bind(global) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        log::warn!("Failed to bind PipeWire metadata: {e}");
+                        return;
+                    }
+                };
+                let listener = metadata
+                    .add_listener_local()
+                    .property({
+                        let state = Rc::downgrade(self);
+                        move |subject, key, _, value| {
+                            let Some(state) = state.upgrade() else {
+                                return 0;
+                            };
+                            if subject != 0 {
+                                return 0;
+                            }
+                            let name = value.and_then(parse_name).unwrap_or_default();
+                            match key {
+                                Some("default.audio.sink") => {
+                                    state.update(|x| x.default_sink = name);
+                                }
+                                Some("default.audio.source") => {
+                                    state.update(|x| x.default_source = name);
+                                }
+                                // everything was cleared
+                                None => state.update(|x| {
+                                    x.default_sink.clear();
+                                    x.default_source.clear();
+                                }),
+                                _ => (),
+                            }
+                            0
+                        }
+                    })
+                    .register();
+                *self.metadata.borrow_mut() = Some((metadata, listener));
+            }
+            _ => (),
+        }
+    }
+
+    fn remove(&self, id: u32) {
+        if self.nodes.borrow_mut().remove(&id).is_some() {
+            self.update(|x| {
+                x.nodes.remove(&id);
+            });
+        }
+        if self.devices.borrow_mut().remove(&id).is_some() {
+            self.update(|x| {
+                x.cards.remove(&id);
+            });
+        }
+    }
+
+    fn run(&self, command: Command) {
+        match command {
+            Command::SetVolume(id, level) => {
+                let Some(target) = self.target(id) else {
+                    return;
+                };
+                let channels = target.channels();
+                if channels == 0 {
+                    log::warn!(
+                        "Failed to set volume of PipeWire node {id}: its channels aren't known yet"
+                    );
+                    return;
+                }
+                let linear = (level / 100.0).max(0.0).powi(3) as f32;
+                self.set_props(
+                    id,
+                    target,
+                    sys::SPA_PROP_channelVolumes,
+                    Value::ValueArray(ValueArray::Float(vec![linear; channels])),
+                );
+            }
+            Command::SetMute(id, muted) => {
+                if let Some(target) = self.target(id) {
+                    self.set_props(id, target, sys::SPA_PROP_mute, Value::Bool(muted));
+                }
+            }
+            Command::SetDefault(kind, name) => {
+                let key = match kind {
+                    Kind::Output => "default.configured.audio.sink",
+                    Kind::Input => "default.configured.audio.source",
+                };
+                let value = serde_json::json!({ "name": name }).to_string();
+                match &*self.metadata.borrow() {
+                    Some((metadata, _)) => {
+                        metadata.set_property(0, key, Some("Spa:String:JSON"), Some(&value));
+                    }
+                    None => log::error!(
+                        "Failed to set default device: PipeWire has no default metadata"
+                    ),
+                }
+            }
+            Command::Move(id, serial) => match &*self.metadata.borrow() {
+                Some((metadata, _)) => {
+                    metadata.set_property(id, "target.object", Some("Spa:Id"), Some(&serial));
+                }
+                None => log::error!("Failed to move stream {id}: PipeWire has no default metadata"),
+            },
+        }
+    }
+
+    /// Works out where a node's volume should be set.
+    fn target(&self, id: u32) -> Option<Target> {
+        let graph = self.graph.lock().unwrap();
+        let Some(entry) = graph.nodes.get(&id) else {
+            log::warn!("Failed to find PipeWire node {id}.");
+            return None;
+        };
+        let route = entry.card.and_then(|(card, device)| {
+            Some((card, device, graph.cards.get(&card)?.active.get(&device)?))
+        });
+        Some(match route {
+            Some((card, device, route)) => Target::Route {
+                card,
+                index: route.index,
+                device,
+                channels: match route.channels {
+                    0 => entry.channels,
+                    x => x,
+                },
+            },
+            None => Target::Node {
+                channels: entry.channels,
+            },
+        })
+    }
+
+    fn set_props(&self, id: u32, target: Target, key: u32, value: Value) {
+        let props = Value::Object(Object {
+            type_: sys::SPA_TYPE_OBJECT_Props,
+            id: sys::SPA_PARAM_Props,
+            properties: vec![property(key, value)],
+        });
+
+        match target {
+            Target::Route {
+                card,
+                index,
+                device,
+                ..
+            } => {
+                let devices = self.devices.borrow();
+                let Some((proxy, _)) = devices.get(&card) else {
+                    log::warn!("Failed to find PipeWire device {card}.");
+                    return;
+                };
+                let route = Value::Object(Object {
+                    type_: sys::SPA_TYPE_OBJECT_ParamRoute,
+                    id: sys::SPA_PARAM_Route,
+                    properties: vec![
+                        property(sys::SPA_PARAM_ROUTE_index, Value::Int(index)),
+                        property(sys::SPA_PARAM_ROUTE_device, Value::Int(device)),
+                        property(sys::SPA_PARAM_ROUTE_props, props),
+                        property(sys::SPA_PARAM_ROUTE_save, Value::Bool(true)),
+                    ],
+                });
+                if let Some(bytes) = serialize(&route) {
+                    if let Some(pod) = Pod::from_bytes(&bytes) {
+                        proxy.set_param(ParamType::Route, 0, pod);
+                    }
+                }
+            }
+            Target::Node { .. } => {
+                let nodes = self.nodes.borrow();
+                let Some((node, _)) = nodes.get(&id) else {
+                    log::warn!("Failed to find PipeWire node {id}.");
+                    return;
+                };
+                if let Some(bytes) = serialize(&props) {
+                    if let Some(pod) = Pod::from_bytes(&bytes) {
+                        node.set_param(ParamType::Props, 0, pod);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Entry {
+    /// Reads the names and icon out of a node's properties.
+    fn describe(&mut self, props: &DictRef) {
+        let get = |key| props.get(key).map(String::from);
+        if let Some(x) = get("node.name") {
+            self.name = x;
+        }
+        let card = get("device.id").and_then(|x| x.parse().ok());
+        let device = get("card.profile.device").and_then(|x| x.parse().ok());
+        if let (Some(card), Some(device)) = (card, device) {
+            self.card = Some((card, device));
+        }
+        match self.class {
+            Class::Sink | Class::Source => {
+                if let Some(x) = get("node.description").or_else(|| get("node.nick")) {
+                    self.description = x;
+                }
+            }
+            Class::Playback | Class::Recording => {
+                if let Some(x) = get("application.name").or_else(|| get("media.name")) {
+                    self.description = x;
+                }
+                if let Some(x) =
+                    get("application.icon-name").or_else(|| get("application.process.binary"))
+                {
+                    self.icon = x;
+                }
+            }
+        }
+        if self.description.is_empty() {
+            self.description = self.name.clone();
+        }
+        if self.icon.is_empty() {
+            self.icon = self.description.to_lowercase();
+        }
+    }
+}
+
+/// Runs the PipeWire thread, reporting whether it connected on `ready`.
+///
+/// After the first connection, it keeps reconnecting whenever the server goes away.
+fn run(
+    graph: Arc<Mutex<Graph>>,
+    mut commands: pw::channel::Receiver<Command>,
+    changed: relm4::Sender<Input>,
+    ready: mpsc::Sender<Result<(), Error>>,
+) {
+    pw::init();
+
+    let mut ready = Some(ready);
+    let mut delay = RETRY_DELAY;
+    loop {
+        match connect() {
+            Ok(connection) => {
+                match ready.take() {
+                    Some(x) => x.send(Ok(())).unwrap(),
+                    None => log::info!("Reconnected to PipeWire."),
+                }
+                delay = RETRY_DELAY;
+
+                commands = serve(connection, &graph, &changed, commands);
+                log::error!("Lost connection to PipeWire, reconnecting.");
+            }
+            Err(e) => {
+                if let Some(x) = ready.take() {
+                    x.send(Err(e.to_string().into())).unwrap();
+                    return;
+                }
+            }
+        }
+
+        std::thread::sleep(delay);
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+}
+
+type Connection = (
+    pw::main_loop::MainLoop,
+    pw::context::Context,
+    pw::core::Core,
+    Registry,
+);
+
+fn connect() -> Result<Connection, pw::Error> {
+    let main_loop = pw::main_loop::MainLoop::new(None)?;
+    let context = pw::context::Context::new(&main_loop)?;
+    let core = context.connect(None)?;
+    let registry = core.get_registry()?;
+    Ok((main_loop, context, core, registry))
+}
+
+/// Mirrors the graph and runs commands until the connection drops,
+/// then hands the commands back for the next connection.
+fn serve(
+    (main_loop, _context, core, registry): Connection,
+    graph: &Arc<Mutex<Graph>>,
+    changed: &relm4::Sender<Input>,
+    commands: pw::channel::Receiver<Command>,
+) -> pw::channel::Receiver<Command> {
+    let registry = Rc::new(registry);
+    let state = Rc::new(State {
+        graph: graph.clone(),
+        changed: changed.clone(),
+        registry: Rc::downgrade(&registry),
+        nodes: RefCell::new(BTreeMap::new()),
+        devices: RefCell::new(BTreeMap::new()),
+        metadata: RefCell::new(None),
+    });
+
+    let _core_listener = core
+        .add_listener_local()
+        .error({
+            let main_loop = main_loop.clone();
+            move |id, _, _, message| {
+                log::error!("PipeWire error on object {id}: {message}");
+                if id == pw::core::PW_ID_CORE {
+                    main_loop.quit();
+                }
+            }
+        })
+        .register();
+    let _registry_listener = registry
+        .add_listener_local()
+        .global({
+            let state = state.clone();
+            move |global| state.add(global)
+        })
+        .global_remove({
+            let state = state.clone();
+            move |id| state.remove(id)
+        })
+        .register();
+    let commands = commands.attach(main_loop.loop_(), {
+        let state = state.clone();
+        move |command| state.run(command)
+    });
+
+    main_loop.run();
+
+    // the next connection announces everything again
+    state.update(|x| *x = Graph::default());
+    commands.deattach()
+}
+
+/// Returns the channel volumes and mute state in a node's `Props` parameter.
+fn parse_props(param: &Pod) -> (Option<Vec<f32>>, Option<bool>) {
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(param.as_bytes())
+    else {
+        return (None, None);
+    };
+    read_props(object)
+}
+
+/// Returns the channel volumes and mute state in a `Props` object.
+fn read_props(object: Object) -> (Option<Vec<f32>>, Option<bool>) {
+    let mut volumes = None;
+    let mut muted = None;
+    for property in object.properties {
+        match (property.key, property.value) {
+            (sys::SPA_PROP_channelVolumes, Value::ValueArray(ValueArray::Float(x))) => {
+                volumes = Some(x);
+            }
+            (sys::SPA_PROP_mute, Value::Bool(x)) => muted = Some(x),
+            _ => (),
+        }
+    }
+    (volumes, muted)
+}
+
+/// Reads a device's `EnumRoute` or `Route` parameter.
+fn parse_route(param: &Pod) -> Option<Route> {
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(param.as_bytes())
+    else {
+        return None;
+    };
+
+    let mut route = Route::default();
+    for property in object.properties {
+        match (property.key, property.value) {
+            (sys::SPA_PARAM_ROUTE_index, Value::Int(x)) => route.index = x,
+            (sys::SPA_PARAM_ROUTE_direction, Value::Id(x)) => route.direction = x.0,
+            (sys::SPA_PARAM_ROUTE_description, Value::String(x)) => route.description = x,
+            (sys::SPA_PARAM_ROUTE_devices, Value::ValueArray(ValueArray::Int(x))) => {
+                route.devices = x;
+            }
+            (sys::SPA_PARAM_ROUTE_device, Value::Int(x)) => route.device = Some(x),
+            (sys::SPA_PARAM_ROUTE_props, Value::Object(x)) => {
+                route.channels = read_props(x).0.map_or(0, |x| x.len());
+            }
+            _ => (),
+        }
+    }
+    Some(route)
+}
+
+fn property(key: u32, value: Value) -> Property {
+    Property {
+        key,
+        flags: PropertyFlags::empty(),
+        value,
+    }
+}
+
+fn serialize(value: &Value) -> Option<Vec<u8>> {
+    match PodSerializer::serialize(Cursor::new(Vec::new()), value) {
+        Ok((x, _)) => Some(x.into_inner()),
+        Err(e) => {
+            log::error!("Failed to serialize PipeWire parameters: {e:?}");
+            None
+        }
+    }
+}
+
+/// Converts PipeWire's linear channel volumes into the cubic percentage PulseAudio shows.
+fn linear_to_percentage(volumes: &[f32]) -> f64 {
+    if volumes.is_empty() {
+        return 0.0;
+    }
+    let average = volumes.iter().map(|x| *x as f64).sum::<f64>() / volumes.len() as f64;
+    average.cbrt() * 100.0
+}
+
+/// Reads the node name out of metadata like `{"name":"alsa_output.pci-0000_00_1f.3"}`.
+fn parse_name(value: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(value)
+        .ok()?
+        .get("name")?
+        .as_str()
+        .map(String::from)
+}
@@ -0,0 +1,291 @@
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::rc::Rc;
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::time::Instant;
+
+use libpulse_binding::context::subscribe::InterestMaskSet;
+use libpulse_binding::context::{Context, State};
+use libpulse_binding::error::PAErr;
+use libpulse_binding::mainloop::api::Mainloop as _;
+use libpulse_binding::mainloop::events::io::FlagSet as IoEventFlagSet;
+use libpulse_binding::mainloop::standard::Mainloop;
+
+use super::{
+    utils, Backend, Device, Error, Input, Kind, Stream, Volume, MAX_RETRY_DELAY, RETRY_DELAY,
+};
+
+/// A sound server spoken to through libpulse, which also covers PipeWire's pulse shim.
+///
+/// libpulse's main loop can't leave the thread that made it, so a thread of its own runs it.
+/// Requests reach that thread over a channel, with a byte on a socket the main loop watches
+/// to wake it up.
+pub struct PulseAudio {
+    requests: mpsc::Sender<Request>,
+    wake: UnixStream,
+}
+
+type Request = Box<dyn FnOnce(&mut Mainloop, &mut Context) + Send>;
+
+impl PulseAudio {
+    pub fn new(changed: &relm4::Sender<Input>) -> Result<Self, Error> {
+        let (requests, receiver) = mpsc::channel();
+        let (wake, woken) = UnixStream::pair()?;
+        let (ready, connected) = mpsc::channel();
+
+        std::thread::spawn({
+            let changed = changed.clone();
+            move || run(receiver, woken, changed, ready)
+        });
+        connected.recv()??;
+
+        Ok(Self { requests, wake })
+    }
+
+    /// Runs `request` on the PulseAudio thread and waits for its result.
+    fn call<T, F>(&mut self, request: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Mainloop, &mut Context) -> Result<T, PAErr> + Send + 'static,
+    {
+        let (reply, result) = mpsc::channel();
+        self.requests
+            .send(Box::new(move |main_loop, context| {
+                let _ = reply.send(request(main_loop, context));
+            }))
+            .map_err(|_| Error::from("the PulseAudio thread has stopped"))?;
+        self.wake.write_all(&[0])?;
+
+        // requests are dropped unanswered while the server is away
+        Ok(result
+            .recv()
+            .map_err(|_| Error::from("not connected to PulseAudio"))??)
+    }
+}
+
+impl Backend for PulseAudio {
+    fn volume(&mut self, kind: Kind) -> Result<Volume, Error> {
+        let (volumes, muted) = self.call(move |main_loop, context| match kind {
+            Kind::Output => utils::get_output(main_loop, context),
+            Kind::Input => utils::get_input(main_loop, context),
+        })?;
+        Ok(Volume {
+            level: utils::volume_to_percentage(volumes.get()[0]),
+            muted,
+        })
+    }
+
+    fn set_volume(&mut self, kind: Kind, level: f64) -> Result<(), Error> {
+        self.call(move |main_loop, context| match kind {
+            Kind::Output => {
+                let (mut volumes, _) = utils::get_output(main_loop, context)?;
+                utils::map_volumes(&mut volumes, |_| level);
+                utils::set_output_volumes(main_loop, context, &volumes)
+            }
+            Kind::Input => {
+                let (mut volumes, _) = utils::get_input(main_loop, context)?;
+                utils::map_volumes(&mut volumes, |_| level);
+                utils::set_input_volumes(main_loop, context, &volumes)
+            }
+        })
+    }
+
+    fn set_mute(&mut self, kind: Kind, muted: bool) -> Result<(), Error> {
+        self.call(move |main_loop, context| match kind {
+            Kind::Output => utils::set_output_mute(main_loop, context, muted),
+            Kind::Input => utils::set_input_mute(main_loop, context, muted),
+        })
+    }
+
+    fn devices(&mut self, kind: Kind) -> Result<(Vec<Device>, String), Error> {
+        self.call(move |main_loop, context| {
+            let (sink, source) = utils::get_defaults(main_loop, context)?;
+            Ok(match kind {
+                Kind::Output => (utils::get_sinks(main_loop, context)?, sink),
+                Kind::Input => (utils::get_sources(main_loop, context)?, source),
+            })
+        })
+    }
+
+    fn set_default(&mut self, kind: Kind, name: &str) -> Result<(), Error> {
+        let name = name.to_owned();
+        self.call(move |main_loop, context| match kind {
+            Kind::Output => utils::set_default_sink(main_loop, context, &name),
+            Kind::Input => utils::set_default_source(main_loop, context, &name),
+        })
+    }
+
+    fn move_streams(&mut self, kind: Kind, name: &str) -> Result<(), Error> {
+        let name = name.to_owned();
+        self.call(move |main_loop, context| match kind {
+            Kind::Output => utils::move_sink_inputs(main_loop, context, &name),
+            Kind::Input => utils::move_source_outputs(main_loop, context, &name),
+        })
+    }
+
+    fn streams(&mut self) -> Result<Vec<Stream>, Error> {
+        self.call(|main_loop, context| utils::get_sink_inputs(main_loop, context))
+    }
+
+    fn set_stream_volume(&mut self, index: u32, level: f64) -> Result<(), Error> {
+        self.call(move |main_loop, context| {
+            let mut volumes = utils::get_sink_input_volumes(main_loop, context, index)?;
+            utils::map_volumes(&mut volumes, |_| level);
+            utils::set_sink_input_volumes(main_loop, context, index, &volumes)
+        })
+    }
+
+    fn set_stream_mute(&mut self, index: u32, muted: bool) -> Result<(), Error> {
+        self.call(move |main_loop, context| {
+            utils::set_sink_input_mute(main_loop, context, index, muted)
+        })
+    }
+
+    fn recording(&mut self) -> Result<bool, Error> {
+        self.call(|main_loop, context| {
+            Ok(!utils::get_source_outputs(main_loop, context)?.is_empty())
+        })
+    }
+}
+
+/// Runs the PulseAudio thread, reporting whether it connected on `ready`.
+///
+/// After the first connection, it keeps reconnecting whenever the server goes away.
+fn run(
+    requests: mpsc::Receiver<Request>,
+    mut woken: UnixStream,
+    changed: relm4::Sender<Input>,
+    ready: mpsc::Sender<Result<(), Error>>,
+) {
+    let Some(mut main_loop) = Mainloop::new() else {
+        ready
+            .send(Err("couldn't create a main loop".into()))
+            .unwrap();
+        return;
+    };
+    if let Err(e) = woken.set_nonblocking(true) {
+        ready.send(Err(e.into())).unwrap();
+        return;
+    }
+    let wake = Rc::new(Cell::new(false));
+    let _wake_event = main_loop.new_io_event(
+        woken.as_raw_fd(),
+        IoEventFlagSet::INPUT,
+        Box::new({
+            let wake = wake.clone();
+            move |_, _, _| wake.set(true)
+        }),
+    );
+
+    let mut ready = Some(ready);
+    let mut delay = RETRY_DELAY;
+    loop {
+        match utils::connect(&mut main_loop) {
+            Ok(mut context) => {
+                match ready.take() {
+                    Some(x) => x.send(Ok(())).unwrap(),
+                    None => {
+                        log::info!("Reconnected to PulseAudio.");
+                        changed.emit(Input::Refresh);
+                    }
+                }
+                delay = RETRY_DELAY;
+
+                if !serve(
+                    &mut main_loop,
+                    &mut context,
+                    &requests,
+                    &mut woken,
+                    &wake,
+                    &changed,
+                ) {
+                    return;
+                }
+                log::error!(
+                    "Lost connection to PulseAudio, reconnecting: {}",
+                    context.errno()
+                );
+            }
+            Err(()) => {
+                if let Some(x) = ready.take() {
+                    x.send(Err("the server refused the connection".into()))
+                        .unwrap();
+                    return;
+                }
+            }
+        }
+
+        // fail requests while waiting rather than keep the worker waiting too
+        let deadline = Instant::now() + delay;
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            match requests.recv_timeout(timeout) {
+                Ok(_) => (),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+}
+
+/// Answers requests and reports changes until the connection drops.
+///
+/// Returns false once the worker has gone away.
+fn serve(
+    main_loop: &mut Mainloop,
+    context: &mut Context,
+    requests: &mpsc::Receiver<Request>,
+    woken: &mut UnixStream,
+    wake: &Cell<bool>,
+    changed: &relm4::Sender<Input>,
+) -> bool {
+    let events = Rc::new(Cell::new(false));
+    context.set_subscribe_callback(Some(Box::new({
+        let events = events.clone();
+        move |_, _, _| events.set(true)
+    })));
+    context.subscribe(
+        InterestMaskSet::SINK
+            | InterestMaskSet::SOURCE
+            | InterestMaskSet::SERVER
+            | InterestMaskSet::SINK_INPUT
+            | InterestMaskSet::SOURCE_OUTPUT,
+        |success| {
+            if !success {
+                log::error!("Failed to subscribe to PulseAudio events.");
+            }
+        },
+    );
+
+    loop {
+        let result = utils::run_until(main_loop, |_| {
+            events.get() || wake.get() || !context.get_state().is_good()
+        });
+        match result {
+            Ok(None) if context.get_state() == State::Ready => (),
+            Ok(_) => return true,
+            Err(e) => {
+                log::error!("Error in PulseAudio main loop: {e}");
+                return true;
+            }
+        }
+
+        if wake.take() {
+            // the bytes only wake the loop up, the requests come through the channel
+            while woken.read(&mut [0; 64]).is_ok_and(|x| x > 0) {}
+            loop {
+                match requests.try_recv() {
+                    Ok(request) => request(main_loop, context),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return false,
+                }
+            }
+        }
+
+        if events.take() {
+            changed.emit(Input::Refresh);
+        }
+    }
+}
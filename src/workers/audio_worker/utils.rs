@@ -1,9 +1,13 @@
 use libpulse_binding::context::{Context, State};
-use libpulse_binding::error::PAErr;
+use libpulse_binding::error::{Code, PAErr};
 use libpulse_binding::mainloop::standard::Mainloop;
+use libpulse_binding::operation::{Operation, State as OperationState};
+use libpulse_binding::proplist::properties;
 use libpulse_binding::volume::{ChannelVolumes, Volume};
 use std::sync::{Arc, Mutex};
 
+use super::{Device, Stream};
+
 // code is from https://github.com/de-vri-es/volume-ctl
 
 /*
@@ -33,22 +37,23 @@ OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 */
 
-pub fn get_output_volumes(
+/// Returns the volume of each channel of the default sink and whether it's muted.
+pub fn get_output(
     main_loop: &mut Mainloop,
     context: &Context,
-) -> Result<ChannelVolumes, PAErr> {
+) -> Result<(ChannelVolumes, bool), PAErr> {
     run(main_loop, move |output| {
         context
             .introspect()
             .get_sink_info_by_name("@DEFAULT_SINK@", move |info| match info {
                 libpulse_binding::callbacks::ListResult::Item(x) => {
-                    *output.lock().unwrap() = Some(Ok(x.volume));
+                    *output.lock().unwrap() = Some(Ok((x.volume, x.mute)));
                 }
                 libpulse_binding::callbacks::ListResult::End => {}
                 libpulse_binding::callbacks::ListResult::Error => {
                     *output.lock().unwrap() = Some(Err(()));
                 }
-            });
+            })
     })?
     .map_err(|()| context.errno())
 }
@@ -102,12 +107,19 @@ pub fn connect(main_loop: &mut Mainloop) -> Result<Context, ()> {
     Ok(context)
 }
 
+/// Iterates the main loop until `condition` holds, returning the exit code if it quits first.
+///
+/// The condition is checked before blocking, so anything that happened
+/// while the loop was iterated elsewhere isn't missed.
 pub fn run_until<F>(main_loop: &mut Mainloop, condition: F) -> Result<Option<i32>, PAErr>
 where
     F: Fn(&mut Mainloop) -> bool,
 {
     use libpulse_binding::mainloop::standard::IterateResult;
     loop {
+        if condition(main_loop) {
+            return Ok(None);
+        };
         match main_loop.iterate(true) {
             IterateResult::Err(e) => {
                 return Err(e);
@@ -117,30 +129,36 @@ where
             }
             IterateResult::Success(_iterations) => (),
         }
-        if condition(main_loop) {
-            return Ok(None);
-        };
     }
 }
 
-pub fn run<F, T>(main_loop: &mut Mainloop, operation: F) -> Result<T, PAErr>
+/// Starts an operation and iterates the main loop until its callback fills in the output.
+///
+/// Fails if the operation ends without any output, like when the connection drops.
+pub fn run<F, T, C>(main_loop: &mut Mainloop, operation: F) -> Result<T, PAErr>
 where
-    F: FnOnce(Arc<Mutex<Option<T>>>),
+    F: FnOnce(Arc<Mutex<Option<T>>>) -> Operation<C>,
+    C: ?Sized,
 {
     use libpulse_binding::mainloop::standard::IterateResult;
     let output = Arc::new(Mutex::new(None));
-    operation(output.clone());
+    let operation = operation(output.clone());
 
     loop {
         if let Some(value) = output.lock().unwrap().take() {
             return Ok(value);
         }
+        match operation.get_state() {
+            OperationState::Running => (),
+            OperationState::Done => return Err(PAErr::from(Code::NoEntity)),
+            OperationState::Cancelled => return Err(PAErr::from(Code::ConnectionTerminated)),
+        }
         match main_loop.iterate(true) {
             IterateResult::Err(e) => {
                 return Err(e);
             }
-            IterateResult::Quit(code) => {
-                std::process::exit(code.0);
+            IterateResult::Quit(_code) => {
+                return Err(PAErr::from(Code::Killed));
             }
             IterateResult::Success(_iterations) => (),
         }
@@ -168,11 +186,374 @@ pub fn set_output_volumes(
                     *output.lock().unwrap() = Some(Err(()));
                 }
             })),
-        );
+        )
+    })?
+    .map_err(|()| context.errno())
+}
+
+pub fn set_output_mute(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    mute: bool,
+) -> Result<(), PAErr> {
+    run(main_loop, move |output| {
+        context.introspect().set_sink_mute_by_name(
+            "@DEFAULT_SINK@",
+            mute,
+            Some(Box::new(move |success| {
+                if success {
+                    *output.lock().unwrap() = Some(Ok(()));
+                } else {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })),
+        )
+    })?
+    .map_err(|()| context.errno())
+}
+
+/// Returns the names of the default sink and source.
+pub fn get_defaults(
+    main_loop: &mut Mainloop,
+    context: &Context,
+) -> Result<(String, String), PAErr> {
+    run(main_loop, move |output| {
+        context.introspect().get_server_info(move |info| {
+            let name = |x: &Option<std::borrow::Cow<str>>| {
+                x.as_deref().map(String::from).unwrap_or_default()
+            };
+            *output.lock().unwrap() = Some((
+                name(&info.default_sink_name),
+                name(&info.default_source_name),
+            ))
+        })
+    })
+}
+
+pub fn get_sinks(main_loop: &mut Mainloop, context: &Context) -> Result<Vec<Device>, PAErr> {
+    run(main_loop, move |output| {
+        let mut sinks = Vec::new();
+        context
+            .introspect()
+            .get_sink_info_list(move |info| match info {
+                libpulse_binding::callbacks::ListResult::Item(x) => {
+                    let description = |x: &libpulse_binding::context::introspect::SinkPortInfo| {
+                        x.description.as_deref().unwrap_or_default().to_string()
+                    };
+                    sinks.push(Device {
+                        name: x.name.as_deref().unwrap_or_default().to_string(),
+                        description: x.description.as_deref().unwrap_or_default().to_string(),
+                        ports: x.ports.iter().map(description).collect(),
+                        active_port: x.active_port.as_deref().map(description),
+                    });
+                }
+                libpulse_binding::callbacks::ListResult::End => {
+                    *output.lock().unwrap() = Some(Ok(std::mem::take(&mut sinks)));
+                }
+                libpulse_binding::callbacks::ListResult::Error => {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })
+    })?
+    .map_err(|()| context.errno())
+}
+
+/// Returns every source except the monitors of sinks.
+pub fn get_sources(main_loop: &mut Mainloop, context: &Context) -> Result<Vec<Device>, PAErr> {
+    run(main_loop, move |output| {
+        let mut sources = Vec::new();
+        context
+            .introspect()
+            .get_source_info_list(move |info| match info {
+                libpulse_binding::callbacks::ListResult::Item(x) => {
+                    if x.monitor_of_sink.is_some() {
+                        return;
+                    }
+                    let description =
+                        |x: &libpulse_binding::context::introspect::SourcePortInfo| {
+                            x.description.as_deref().unwrap_or_default().to_string()
+                        };
+                    sources.push(Device {
+                        name: x.name.as_deref().unwrap_or_default().to_string(),
+                        description: x.description.as_deref().unwrap_or_default().to_string(),
+                        ports: x.ports.iter().map(description).collect(),
+                        active_port: x.active_port.as_deref().map(description),
+                    });
+                }
+                libpulse_binding::callbacks::ListResult::End => {
+                    *output.lock().unwrap() = Some(Ok(std::mem::take(&mut sources)));
+                }
+                libpulse_binding::callbacks::ListResult::Error => {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })
+    })?
+    .map_err(|()| context.errno())
+}
+
+pub fn set_default_sink(
+    main_loop: &mut Mainloop,
+    context: &mut Context,
+    name: &str,
+) -> Result<(), PAErr> {
+    let result = run(main_loop, |output| {
+        context.set_default_sink(name, move |success| *output.lock().unwrap() = Some(success))
+    })?;
+    match result {
+        true => Ok(()),
+        false => Err(context.errno()),
+    }
+}
+
+/// Returns every playing stream.
+pub fn get_sink_inputs(main_loop: &mut Mainloop, context: &Context) -> Result<Vec<Stream>, PAErr> {
+    run(main_loop, move |output| {
+        let mut streams = Vec::new();
+        context
+            .introspect()
+            .get_sink_input_info_list(move |info| match info {
+                libpulse_binding::callbacks::ListResult::Item(x) => {
+                    let property = |key| x.proplist.get_str(key);
+                    let name = property(properties::APPLICATION_NAME)
+                        .or_else(|| x.name.as_deref().map(String::from))
+                        .unwrap_or_default();
+                    let icon = property(properties::APPLICATION_ICON_NAME)
+                        .or_else(|| property(properties::APPLICATION_PROCESS_BINARY))
+                        .unwrap_or_else(|| name.to_lowercase());
+                    streams.push(Stream {
+                        index: x.index,
+                        name,
+                        icon,
+                        volume: super::Volume {
+                            level: volume_to_percentage(x.volume.avg()),
+                            muted: x.mute,
+                        },
+                    });
+                }
+                libpulse_binding::callbacks::ListResult::End => {
+                    *output.lock().unwrap() = Some(Ok(std::mem::take(&mut streams)));
+                }
+                libpulse_binding::callbacks::ListResult::Error => {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })
     })?
     .map_err(|()| context.errno())
 }
 
+/// Returns the volume of each channel of a playing stream.
+pub fn get_sink_input_volumes(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    index: u32,
+) -> Result<ChannelVolumes, PAErr> {
+    run(main_loop, move |output| {
+        context
+            .introspect()
+            .get_sink_input_info(index, move |info| match info {
+                libpulse_binding::callbacks::ListResult::Item(x) => {
+                    *output.lock().unwrap() = Some(Ok(x.volume));
+                }
+                libpulse_binding::callbacks::ListResult::End => {}
+                libpulse_binding::callbacks::ListResult::Error => {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })
+    })?
+    .map_err(|()| context.errno())
+}
+
+pub fn set_sink_input_volumes(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    index: u32,
+    volumes: &ChannelVolumes,
+) -> Result<(), PAErr> {
+    run(main_loop, move |output| {
+        context.introspect().set_sink_input_volume(
+            index,
+            volumes,
+            Some(Box::new(move |success| {
+                if success {
+                    *output.lock().unwrap() = Some(Ok(()));
+                } else {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })),
+        )
+    })?
+    .map_err(|()| context.errno())
+}
+
+pub fn set_sink_input_mute(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    index: u32,
+    mute: bool,
+) -> Result<(), PAErr> {
+    run(main_loop, move |output| {
+        context.introspect().set_sink_input_mute(
+            index,
+            mute,
+            Some(Box::new(move |success| {
+                if success {
+                    *output.lock().unwrap() = Some(Ok(()));
+                } else {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })),
+        )
+    })?
+    .map_err(|()| context.errno())
+}
+
+/// Moves every playing stream onto the sink called `name`.
+pub fn move_sink_inputs(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    name: &str,
+) -> Result<(), PAErr> {
+    for index in get_sink_inputs(main_loop, context)?
+        .into_iter()
+        .map(|x| x.index)
+    {
+        let moved = run(main_loop, |output| {
+            context.introspect().move_sink_input_by_name(
+                index,
+                name,
+                Some(Box::new(move |success| {
+                    *output.lock().unwrap() = Some(success);
+                })),
+            )
+        })?;
+        // streams can end while this is running
+        if !moved {
+            log::warn!("Failed to move stream {index}: {}", context.errno());
+        }
+    }
+    Ok(())
+}
+
+/// Returns the volume of each channel of the default source and whether it's muted.
+pub fn get_input(
+    main_loop: &mut Mainloop,
+    context: &Context,
+) -> Result<(ChannelVolumes, bool), PAErr> {
+    run(main_loop, move |output| {
+        context
+            .introspect()
+            .get_source_info_by_name("@DEFAULT_SOURCE@", move |info| match info {
+                libpulse_binding::callbacks::ListResult::Item(x) => {
+                    *output.lock().unwrap() = Some(Ok((x.volume, x.mute)));
+                }
+                libpulse_binding::callbacks::ListResult::End => {}
+                libpulse_binding::callbacks::ListResult::Error => {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })
+    })?
+    .map_err(|()| context.errno())
+}
+
+pub fn set_input_volumes(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    volumes: &ChannelVolumes,
+) -> Result<(), PAErr> {
+    run(main_loop, move |output| {
+        context.introspect().set_source_volume_by_name(
+            "@DEFAULT_SOURCE@",
+            volumes,
+            Some(Box::new(move |success| {
+                if success {
+                    *output.lock().unwrap() = Some(Ok(()));
+                } else {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })),
+        )
+    })?
+    .map_err(|()| context.errno())
+}
+
+pub fn set_input_mute(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    mute: bool,
+) -> Result<(), PAErr> {
+    run(main_loop, move |output| {
+        context.introspect().set_source_mute_by_name(
+            "@DEFAULT_SOURCE@",
+            mute,
+            Some(Box::new(move |success| {
+                if success {
+                    *output.lock().unwrap() = Some(Ok(()));
+                } else {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })),
+        )
+    })?
+    .map_err(|()| context.errno())
+}
+
+pub fn set_default_source(
+    main_loop: &mut Mainloop,
+    context: &mut Context,
+    name: &str,
+) -> Result<(), PAErr> {
+    let result = run(main_loop, |output| {
+        context.set_default_source(name, move |success| *output.lock().unwrap() = Some(success))
+    })?;
+    match result {
+        true => Ok(()),
+        false => Err(context.errno()),
+    }
+}
+
+/// Returns the indices of every recording stream.
+pub fn get_source_outputs(main_loop: &mut Mainloop, context: &Context) -> Result<Vec<u32>, PAErr> {
+    run(main_loop, move |output| {
+        let mut indices = Vec::new();
+        context
+            .introspect()
+            .get_source_output_info_list(move |info| match info {
+                libpulse_binding::callbacks::ListResult::Item(x) => indices.push(x.index),
+                libpulse_binding::callbacks::ListResult::End => {
+                    *output.lock().unwrap() = Some(Ok(std::mem::take(&mut indices)));
+                }
+                libpulse_binding::callbacks::ListResult::Error => {
+                    *output.lock().unwrap() = Some(Err(()));
+                }
+            })
+    })?
+    .map_err(|()| context.errno())
+}
+
+/// Moves every recording stream onto the source called `name`.
+pub fn move_source_outputs(
+    main_loop: &mut Mainloop,
+    context: &Context,
+    name: &str,
+) -> Result<(), PAErr> {
+    for index in get_source_outputs(main_loop, context)? {
+        let moved = run(main_loop, |output| {
+            context.introspect().move_source_output_by_name(
+                index,
+                name,
+                Some(Box::new(move |success| {
+                    *output.lock().unwrap() = Some(success);
+                })),
+            )
+        })?;
+        // streams can end while this is running
+        if !moved {
+            log::warn!("Failed to move stream {index}: {}", context.errno());
+        }
+    }
+    Ok(())
+}
+
 pub fn percentage_to_volume(factor: f64) -> Volume {
     let range = Volume::NORMAL.0 as f64 - Volume::MUTED.0 as f64;
     Volume((Volume::MUTED.0 as f64 + factor * range / 100.0) as u32)
@@ -181,7 +562,7 @@ pub fn percentage_to_volume(factor: f64) -> Volume {
 pub fn map_volumes<F: FnMut(f64) -> f64>(volumes: &mut ChannelVolumes, mut action: F) {
     for volume in volumes.get_mut() {
         let factor = volume_to_percentage(*volume);
-        let adjusted = action(factor).clamp(0.0, 125.0);
+        let adjusted = action(factor).max(0.0);
         *volume = percentage_to_volume(adjusted);
     }
 }
@@ -1,11 +1,149 @@
-use starship_battery::Battery;
+use starship_battery::{Battery, Manager};
 
 use std::time::Duration;
 
 use relm4::{ComponentSender, Worker};
 
+use crate::config::BatteryConfig;
+
 pub struct AsyncHandler {
-    battery: Option<Battery>,
+    interval: Duration,
+}
+
+/// Whether a battery is charging.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum State {
+    Charging,
+    Discharging,
+    Full,
+    #[default]
+    Unknown,
+}
+
+impl From<starship_battery::State> for State {
+    fn from(x: starship_battery::State) -> Self {
+        match x {
+            starship_battery::State::Charging => Self::Charging,
+            starship_battery::State::Discharging | starship_battery::State::Empty => {
+                Self::Discharging
+            }
+            starship_battery::State::Full => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// One battery pack.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pack {
+    pub name: String,
+    /// Percentage of the energy the pack can hold that's left.
+    pub percent: f32,
+    pub state: State,
+    /// Watts going into or out of the pack.
+    pub power: f32,
+    /// Watts going into the pack, negative when they're going out.
+    rate: f32,
+    // in joules, so packs of different sizes can be combined
+    energy: f32,
+    energy_full: f32,
+}
+
+impl Pack {
+    fn read(name: String, battery: &Battery) -> Self {
+        Self::new(
+            name,
+            battery.state().into(),
+            battery.energy_rate().value,
+            battery.energy().value,
+            battery.energy_full().value,
+        )
+    }
+
+    /// `power` is in watts and `energy` and `energy_full` in joules.
+    fn new(name: String, state: State, power: f32, energy: f32, energy_full: f32) -> Self {
+        // some firmware reports the rate signed and some doesn't, so it's signed here
+        let power = power.abs();
+        Self {
+            name,
+            percent: match energy_full > 0.0 {
+                true => (energy / energy_full * 100.0).round(),
+                false => 0.0,
+            },
+            state,
+            power,
+            rate: match state {
+                State::Charging => power,
+                State::Discharging => -power,
+                _ => 0.0,
+            },
+            energy,
+            energy_full,
+        }
+    }
+}
+
+/// Every battery combined, as if they were one.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Status {
+    /// Percentage of the energy all the batteries can hold that's left.
+    pub percent: f32,
+    pub state: State,
+    pub time_to_empty: Option<Duration>,
+    pub time_to_full: Option<Duration>,
+    /// Watts going into or out of all the batteries.
+    pub power: f32,
+    /// Empty when there are no batteries.
+    pub packs: Vec<Pack>,
+}
+
+impl Status {
+    fn new(packs: Vec<Pack>) -> Self {
+        let energy: f32 = packs.iter().map(|x| x.energy).sum();
+        let energy_full: f32 = packs.iter().map(|x| x.energy_full).sum();
+        // one pack can charge another, so what's left over is what the batteries do together
+        let rate: f32 = packs.iter().map(|x| x.rate).sum();
+        let power = rate.abs();
+
+        let state = if !packs.is_empty() && packs.iter().all(|x| x.state == State::Full) {
+            State::Full
+        } else if rate > 0.0 {
+            State::Charging
+        } else if rate < 0.0 {
+            State::Discharging
+        // without a rate, one pack charging is enough, some laptops only charge one at a time
+        } else if packs.iter().any(|x| x.state == State::Charging) {
+            State::Charging
+        } else if packs.iter().any(|x| x.state == State::Discharging) {
+            State::Discharging
+        } else {
+            State::Unknown
+        };
+
+        // worked out from the totals, since packs can drain one after the other
+        let time = |joules: f32| match power > 0.0 {
+            true => Duration::try_from_secs_f32(joules / power).ok(),
+            false => None,
+        };
+
+        Self {
+            percent: match energy_full > 0.0 {
+                true => (energy / energy_full * 100.0).round(),
+                false => 0.0,
+            },
+            state,
+            time_to_empty: match state {
+                State::Discharging => time(energy),
+                _ => None,
+            },
+            time_to_full: match state {
+                State::Charging => time(energy_full - energy),
+                _ => None,
+            },
+            power,
+            packs,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -15,41 +153,230 @@ pub enum Input {
 
 #[derive(Debug)]
 pub enum Output {
-    UpdateLife(f32),
+    Update(Status),
 }
 
 impl Worker for AsyncHandler {
-    type Init = Option<Battery>;
+    type Init = BatteryConfig;
     type Input = Input;
     type Output = Output;
 
-    fn init(battery: Self::Init, sender: ComponentSender<Self>) -> Self {
+    fn init(config: Self::Init, sender: ComponentSender<Self>) -> Self {
         sender.input(Input::Start);
-        Self { battery }
+        Self {
+            interval: Duration::from_secs(config.interval),
+        }
     }
 
     fn update(&mut self, msg: Input, sender: ComponentSender<Self>) {
-        let interval = Duration::from_secs(5);
+        let interval = self.interval;
         match msg {
             Input::Start => {
-                if let Some(battery) = self.battery.as_mut() {
-                    loop {
-                        match battery.refresh() {
-                            Ok(_) => (),
-                            Err(e) => {
-                                log::error!("Failed to refresh battery: {e}");
-                                return;
-                            }
-                        }
-                        let bat =
-                            (battery.energy().value / battery.energy_full().value * 100.0).round();
-                        sender.output(Output::UpdateLife(bat)).unwrap();
-                        std::thread::sleep(interval);
+                let manager = match Manager::new() {
+                    Ok(x) => x,
+                    Err(e) => {
+                        log::error!("Failed to find batteries: {e}");
+                        sender.output(Output::Update(Status::default())).unwrap();
+                        return;
+                    }
+                };
+
+                let mut count = None;
+                loop {
+                    // batteries are listed again every time, so ones that are
+                    // plugged in or pulled out are noticed
+                    let packs = read_packs(&manager);
+                    if count != Some(packs.len()) {
+                        log::info!("Found {} batteries.", packs.len());
+                        count = Some(packs.len());
                     }
-                } else {
-                    sender.output(Output::UpdateLife(-999.0)).unwrap();
+                    sender.output(Output::Update(Status::new(packs))).unwrap();
+                    std::thread::sleep(interval);
                 }
             }
         }
     }
 }
+
+fn read_packs(manager: &Manager) -> Vec<Pack> {
+    let batteries = match manager.batteries() {
+        Ok(x) => x,
+        Err(e) => {
+            log::error!("Failed to list batteries: {e}");
+            return Vec::new();
+        }
+    };
+    let mut packs: Vec<Pack> = batteries
+        .filter_map(|x| match x {
+            Ok(x) => Some(Pack::read(name(&x), &x)),
+            // a battery pulled out while it's being read
+            Err(e) => {
+                log::warn!("Failed to read battery: {e}");
+                None
+            }
+        })
+        .collect();
+    number_duplicates(&mut packs);
+    packs
+}
+
+/// Numbers packs that share a name, since identical packs without serial numbers
+/// can only be told apart by their order.
+fn number_duplicates(packs: &mut [Pack]) {
+    let names: Vec<String> = packs.iter().map(|x| x.name.clone()).collect();
+    for (i, pack) in packs.iter_mut().enumerate() {
+        if names.iter().filter(|x| **x == pack.name).count() > 1 {
+            let nth = names[..i].iter().filter(|x| **x == pack.name).count() + 1;
+            pack.name = format!("{} {nth}", pack.name);
+        }
+    }
+}
+
+/// Names a pack after its vendor, model and serial number, which stay the same
+/// however the batteries happen to be listed.
+fn name(battery: &Battery) -> String {
+    let parts: Vec<&str> = [battery.vendor(), battery.model(), battery.serial_number()]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .collect();
+    match parts.is_empty() {
+        true => "Battery".to_owned(),
+        false => parts.join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pack holding `energy` of `energy_full` watt-hours.
+    fn pack(state: State, power: f32, energy: f32, energy_full: f32) -> Pack {
+        Pack::new(
+            String::from("Battery"),
+            state,
+            power,
+            energy * 3600.0,
+            energy_full * 3600.0,
+        )
+    }
+
+    #[test]
+    fn no_packs() {
+        let status = Status::new(Vec::new());
+        assert_eq!(status, Status::default());
+    }
+
+    #[test]
+    fn weighs_packs_by_energy() {
+        // 10 of 20 and 40 of 80 is half, however the packs' own percentages average
+        let status = Status::new(vec![
+            pack(State::Discharging, 5.0, 10.0, 20.0),
+            pack(State::Discharging, 5.0, 70.0, 80.0),
+        ]);
+        assert_eq!(status.packs[0].percent, 50.0);
+        assert_eq!(status.packs[1].percent, 88.0);
+        assert_eq!(status.percent, 80.0);
+        assert_eq!(status.state, State::Discharging);
+        assert_eq!(status.power, 10.0);
+        // 80 watt-hours at 10 watts
+        assert_eq!(status.time_to_empty, Some(Duration::from_secs(8 * 3600)));
+        assert_eq!(status.time_to_full, None);
+    }
+
+    #[test]
+    fn full_packs() {
+        let status = Status::new(vec![
+            pack(State::Full, 0.0, 20.0, 20.0),
+            pack(State::Full, 0.0, 80.0, 80.0),
+        ]);
+        assert_eq!(status.percent, 100.0);
+        assert_eq!(status.state, State::Full);
+        assert_eq!(status.time_to_empty, None);
+        assert_eq!(status.time_to_full, None);
+    }
+
+    #[test]
+    fn charging_from_full() {
+        // laptops often charge one pack while the other sits full
+        let status = Status::new(vec![
+            pack(State::Full, 0.0, 20.0, 20.0),
+            pack(State::Charging, 20.0, 40.0, 80.0),
+        ]);
+        assert_eq!(status.state, State::Charging);
+        assert_eq!(status.power, 20.0);
+        // 40 watt-hours to go at 20 watts
+        assert_eq!(status.time_to_full, Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(status.time_to_empty, None);
+    }
+
+    #[test]
+    fn mixed_packs_go_by_the_net_rate() {
+        // one pack charging the other, or a charger too weak for the load
+        let status = Status::new(vec![
+            pack(State::Charging, 4.0, 10.0, 20.0),
+            pack(State::Discharging, -14.0, 40.0, 80.0),
+        ]);
+        assert_eq!(status.state, State::Discharging);
+        assert_eq!(status.power, 10.0);
+        assert_eq!(status.time_to_empty, Some(Duration::from_secs(5 * 3600)));
+
+        let status = Status::new(vec![
+            pack(State::Charging, 15.0, 10.0, 20.0),
+            pack(State::Discharging, 5.0, 40.0, 80.0),
+        ]);
+        assert_eq!(status.state, State::Charging);
+        assert_eq!(status.power, 10.0);
+        assert_eq!(status.time_to_full, Some(Duration::from_secs(5 * 3600)));
+    }
+
+    #[test]
+    fn zero_rate() {
+        // firmware often reports no rate at all, which leaves the state to the packs
+        let status = Status::new(vec![
+            pack(State::Discharging, 0.0, 10.0, 20.0),
+            pack(State::Unknown, 0.0, 40.0, 80.0),
+        ]);
+        assert_eq!(status.state, State::Discharging);
+        assert_eq!(status.power, 0.0);
+        assert_eq!(status.time_to_empty, None);
+
+        let status = Status::new(vec![
+            pack(State::Charging, 0.0, 10.0, 20.0),
+            pack(State::Discharging, 0.0, 40.0, 80.0),
+        ]);
+        assert_eq!(status.state, State::Charging);
+        assert_eq!(status.time_to_full, None);
+
+        let status = Status::new(vec![pack(State::Unknown, 0.0, 10.0, 20.0)]);
+        assert_eq!(status.state, State::Unknown);
+    }
+
+    #[test]
+    fn empty_packs() {
+        let status = Status::new(vec![pack(State::Unknown, 0.0, 0.0, 0.0)]);
+        assert_eq!(status.percent, 0.0);
+        assert_eq!(status.packs[0].percent, 0.0);
+    }
+
+    #[test]
+    fn numbers_packs_that_share_a_name() {
+        let named = |name: &str| Pack {
+            name: name.to_owned(),
+            ..Pack::default()
+        };
+        let mut packs = vec![
+            named("Battery"),
+            named("SMP 5B10W13930"),
+            named("Battery"),
+            named("Battery"),
+        ];
+        number_duplicates(&mut packs);
+        let names: Vec<&str> = packs.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Battery 1", "SMP 5B10W13930", "Battery 2", "Battery 3"]
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::{fs::OpenOptions, path::PathBuf, process::Command};
+
+use zbus::blocking::Connection;
+
+use super::{backlight, ddc};
+use crate::config::BrightnessBackendKind;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Something a display's brightness is controlled through.
+pub enum Device {
+    /// A backlight's directory in sysfs.
+    Backlight(PathBuf),
+    Monitor(ddc::Monitor),
+}
+
+/// A way of setting brightness, tried in the configured order until one works.
+pub trait BrightnessBackend: Send {
+    fn name(&self) -> &'static str;
+    /// Returns whether this backend looks able to set `device`'s brightness.
+    ///
+    /// It's checked before the backend is first used for a device and again after it fails.
+    fn probe(&mut self, device: &Device) -> bool;
+    fn set(&mut self, device: &mut Device, percent: u32) -> Result<(), Error>;
+}
+
+pub fn new(kind: BrightnessBackendKind) -> Box<dyn BrightnessBackend> {
+    match kind {
+        BrightnessBackendKind::Sysfs => Box::new(Sysfs),
+        BrightnessBackendKind::Logind => Box::new(Logind { connection: None }),
+        BrightnessBackendKind::Brightnessctl => Box::new(Brightnessctl { installed: None }),
+        BrightnessBackendKind::Ddc => Box::new(Ddc),
+    }
+}
+
+/// Writes backlights directly, which needs write access from a udev rule or the video group.
+struct Sysfs;
+
+impl BrightnessBackend for Sysfs {
+    fn name(&self) -> &'static str {
+        "sysfs"
+    }
+
+    fn probe(&mut self, device: &Device) -> bool {
+        match device {
+            // opening the file for writing doesn't change anything
+            Device::Backlight(x) => OpenOptions::new()
+                .write(true)
+                .open(x.join("brightness"))
+                .is_ok(),
+            Device::Monitor(_) => false,
+        }
+    }
+
+    fn set(&mut self, device: &mut Device, percent: u32) -> Result<(), Error> {
+        match device {
+            Device::Backlight(x) => Ok(backlight::write(x, percent)?),
+            Device::Monitor(_) => Err("not a backlight".into()),
+        }
+    }
+}
+
+/// Asks logind to set backlights, which it allows for whoever owns the active session.
+struct Logind {
+    connection: Option<Connection>,
+}
+
+impl BrightnessBackend for Logind {
+    fn name(&self) -> &'static str {
+        "logind"
+    }
+
+    fn probe(&mut self, device: &Device) -> bool {
+        if !matches!(device, Device::Backlight(_)) {
+            return false;
+        }
+        if self.connection.is_none() {
+            match Connection::system() {
+                Ok(x) => self.connection = Some(x),
+                Err(e) => {
+                    log::info!("Failed to connect to the system bus for logind: {e}");
+                    return false;
+                }
+            }
+        }
+        // without an active session of ours, such as when started outside logind,
+        // every request would be refused
+        let Some(connection) = self.connection.as_ref() else {
+            return false;
+        };
+        match session_active(connection) {
+            Ok(true) => true,
+            Ok(false) => {
+                log::info!("Not setting brightness through logind, the session isn't active.");
+                false
+            }
+            Err(e) => {
+                log::info!("Failed to find a logind session: {e}");
+                false
+            }
+        }
+    }
+
+    fn set(&mut self, device: &mut Device, percent: u32) -> Result<(), Error> {
+        let Device::Backlight(path) = device else {
+            return Err("not a backlight".into());
+        };
+        let connection = self.connection.as_ref().ok_or("not connected to logind")?;
+        let name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or("unnamed backlight")?;
+
+        let result = connection.call_method(
+            Some("org.freedesktop.login1"),
+            SESSION,
+            Some("org.freedesktop.login1.Session"),
+            "SetBrightness",
+            &("backlight", name, backlight::raw(path, percent)?),
+        );
+        if let Err(e) = result {
+            // connect again when next probed, in case the bus went away
+            self.connection = None;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// The session swirly belongs to, whichever one that is.
+const SESSION: &str = "/org/freedesktop/login1/session/auto";
+
+fn session_active(connection: &Connection) -> zbus::Result<bool> {
+    let reply = connection.call_method(
+        Some("org.freedesktop.login1"),
+        SESSION,
+        Some("org.freedesktop.DBus.Properties"),
+        "Get",
+        &("org.freedesktop.login1.Session", "Active"),
+    )?;
+    let value: zbus::zvariant::OwnedValue = reply.body().deserialize()?;
+    Ok(bool::try_from(value)?)
+}
+
+/// Runs brightnessctl, which goes through logind itself or needs write access like sysfs.
+struct Brightnessctl {
+    installed: Option<bool>,
+}
+
+impl BrightnessBackend for Brightnessctl {
+    fn name(&self) -> &'static str {
+        "brightnessctl"
+    }
+
+    fn probe(&mut self, device: &Device) -> bool {
+        if !matches!(device, Device::Backlight(_)) {
+            return false;
+        }
+        *self.installed.get_or_insert_with(|| {
+            Command::new("brightnessctl")
+                .arg("--version")
+                .output()
+                .is_ok_and(|x| x.status.success())
+        })
+    }
+
+    fn set(&mut self, device: &mut Device, percent: u32) -> Result<(), Error> {
+        let Device::Backlight(path) = device else {
+            return Err("not a backlight".into());
+        };
+        let name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or("unnamed backlight")?;
+
+        let output = Command::new("brightnessctl")
+            .args(["--device", name, "set", &format!("{percent}%")])
+            .output()
+            .inspect_err(|_| self.installed = None)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("brightnessctl {}: {}", output.status, stderr.trim()).into());
+        }
+        Ok(())
+    }
+}
+
+/// Talks to external monitors over DDC/CI.
+struct Ddc;
+
+impl BrightnessBackend for Ddc {
+    fn name(&self) -> &'static str {
+        "ddc"
+    }
+
+    fn probe(&mut self, device: &Device) -> bool {
+        matches!(device, Device::Monitor(_))
+    }
+
+    fn set(&mut self, device: &mut Device, percent: u32) -> Result<(), Error> {
+        match device {
+            Device::Monitor(x) => Ok(x.set_brightness(percent)?),
+            Device::Backlight(_) => Err("not a monitor".into()),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Lists the backlights in `path` by name, so they keep the same order between runs.
+pub fn list(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<PathBuf> = entries
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| x.join("brightness").is_file() && x.join("max_brightness").is_file())
+        .collect();
+    devices.sort();
+    devices
+}
+
+/// Reads a backlight's brightness as a percentage of its maximum.
+pub fn read(device: &Path) -> io::Result<u32> {
+    let brightness = read_value(&device.join("brightness"))?;
+    let max = max(device)?;
+    Ok((brightness as f64 / max as f64 * 100.0).round() as u32)
+}
+
+/// Writes the brightness directly, which needs write access to the backlight.
+pub fn write(device: &Path, percent: u32) -> io::Result<()> {
+    fs::write(device.join("brightness"), raw(device, percent)?.to_string())
+}
+
+/// Converts a percentage into the backlight's own scale.
+pub fn raw(device: &Path, percent: u32) -> io::Result<u32> {
+    let max = max(device)?;
+    Ok((percent.min(100) as f64 / 100.0 * max as f64).round() as u32)
+}
+
+fn max(device: &Path) -> io::Result<u32> {
+    match read_value(&device.join("max_brightness"))? {
+        0 => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "maximum brightness is zero",
+        )),
+        x => Ok(x),
+    }
+}
+
+fn read_value(path: &Path) -> io::Result<u32> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,313 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::fd::AsRawFd,
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+/// The i2c address monitors answer DDC/CI on.
+const ADDRESS: u16 = 0x37;
+/// The VCP feature code for brightness.
+const BRIGHTNESS: u8 = 0x10;
+/// `I2C_SLAVE` from linux/i2c-dev.h, which picks the address later reads and writes go to.
+const I2C_SLAVE: libc::Ioctl = 0x0703;
+
+/// How long monitors need after a request before they answer or take the next one.
+const REPLY_DELAY: Duration = Duration::from_millis(40);
+const SET_DELAY: Duration = Duration::from_millis(50);
+
+/// A connection to one i2c bus, which tests can stand in for a monitor.
+pub trait I2c: Send {
+    /// Writes `data` to the device at `address`.
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()>;
+    /// Fills `buffer` from the device at `address`.
+    fn read(&mut self, address: u16, buffer: &mut [u8]) -> io::Result<()>;
+}
+
+/// Opens i2c buses by name, such as `i2c-4`.
+pub trait Buses: Send {
+    fn open(&self, bus: &str) -> io::Result<Box<dyn I2c>>;
+}
+
+/// The buses the kernel lists in `/dev`.
+pub struct DevBuses(pub PathBuf);
+
+impl Buses for DevBuses {
+    fn open(&self, bus: &str) -> io::Result<Box<dyn I2c>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(self.0.join(bus))?;
+        Ok(Box::new(Device(file)))
+    }
+}
+
+struct Device(File);
+
+impl Device {
+    fn select(&mut self, address: u16) -> io::Result<()> {
+        // SAFETY: I2C_SLAVE takes the address by value and the descriptor stays open
+        let result =
+            unsafe { libc::ioctl(self.0.as_raw_fd(), I2C_SLAVE, libc::c_ulong::from(address)) };
+        match result {
+            ..0 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl I2c for Device {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+        self.select(address)?;
+        self.0.write_all(data)
+    }
+
+    fn read(&mut self, address: u16, buffer: &mut [u8]) -> io::Result<()> {
+        self.select(address)?;
+        self.0.read_exact(buffer)
+    }
+}
+
+/// A monitor whose brightness can be controlled over DDC/CI.
+pub struct Monitor {
+    bus: Box<dyn I2c>,
+    /// The largest brightness the monitor accepts, often but not always 100.
+    max: u16,
+    /// The brightness last read or set.
+    ///
+    /// Asking the monitor takes long enough to hold up other requests,
+    /// so it's only asked once and trusted to change only when swirly sets it.
+    current: u16,
+}
+
+impl Monitor {
+    /// Asks the monitor on `bus` for its brightness, failing if it doesn't speak DDC/CI.
+    pub fn new(mut bus: Box<dyn I2c>) -> io::Result<Self> {
+        let (current, max) = get_vcp(bus.as_mut(), BRIGHTNESS)?;
+        if max == 0 {
+            return Err(invalid("the monitor reports a maximum brightness of zero"));
+        }
+        Ok(Self { bus, max, current })
+    }
+
+    /// Returns the brightness as a percentage.
+    pub fn brightness(&self) -> u32 {
+        (self.current as f64 / self.max as f64 * 100.0).round() as u32
+    }
+
+    pub fn set_brightness(&mut self, percent: u32) -> io::Result<()> {
+        let value = (percent.min(100) as f64 / 100.0 * self.max as f64).round() as u16;
+        set_vcp(self.bus.as_mut(), BRIGHTNESS, value)?;
+        self.current = value;
+        Ok(())
+    }
+}
+
+/// Returns the current and maximum value of a VCP feature.
+fn get_vcp(bus: &mut dyn I2c, code: u8) -> io::Result<(u16, u16)> {
+    bus.write(ADDRESS, &message(&[0x01, code]))?;
+    thread::sleep(REPLY_DELAY);
+
+    // source, length, opcode, result, code, type, max and current, then the checksum
+    let mut reply = [0; 11];
+    bus.read(ADDRESS, &mut reply)?;
+    // replies are checked against the host's address rather than the monitor's
+    let checksum = reply[..10].iter().fold(0x50, |x, y| x ^ y);
+    if reply[10] != checksum {
+        return Err(invalid("the reply's checksum doesn't match"));
+    }
+    if reply[2] != 0x02 || reply[4] != code {
+        return Err(invalid("the reply isn't for the feature asked about"));
+    }
+    if reply[3] != 0 {
+        return Err(invalid("the monitor doesn't support the feature"));
+    }
+
+    Ok((
+        u16::from_be_bytes([reply[8], reply[9]]),
+        u16::from_be_bytes([reply[6], reply[7]]),
+    ))
+}
+
+fn set_vcp(bus: &mut dyn I2c, code: u8, value: u16) -> io::Result<()> {
+    let [high, low] = value.to_be_bytes();
+    bus.write(ADDRESS, &message(&[0x03, code, high, low]))?;
+    thread::sleep(SET_DELAY);
+    Ok(())
+}
+
+/// Wraps a request with the host's address, its length and a checksum.
+fn message(payload: &[u8]) -> Vec<u8> {
+    let mut message = vec![0x51, 0x80 | payload.len() as u8];
+    message.extend_from_slice(payload);
+    // the checksum covers the monitor's address, which the i2c layer sends
+    let checksum = message.iter().fold((ADDRESS << 1) as u8, |x, y| x ^ y);
+    message.push(checksum);
+    message
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+
+    /// What a fake monitor holds, shared with the test so it can look inside.
+    #[derive(Debug, Clone, Default)]
+    pub struct Fake {
+        pub current: u16,
+        pub max: u16,
+        /// Answers with an error code rather than the brightness.
+        pub unsupported: bool,
+        /// Answers with a checksum that doesn't add up.
+        pub corrupt: bool,
+        /// How many times the brightness was asked for.
+        pub reads: usize,
+        /// Every request written, in order.
+        pub requests: Vec<Vec<u8>>,
+    }
+
+    impl Fake {
+        pub fn new(current: u16, max: u16) -> Arc<Mutex<Self>> {
+            Arc::new(Mutex::new(Self {
+                current,
+                max,
+                ..Self::default()
+            }))
+        }
+    }
+
+    /// A monitor answering VCP requests the way a real one does.
+    pub struct FakeMonitor(pub Arc<Mutex<Fake>>);
+
+    impl I2c for FakeMonitor {
+        fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+            assert_eq!(address, ADDRESS);
+            let (body, checksum) = data.split_at(data.len() - 1);
+            assert_eq!(body.iter().fold(0x6e, |x, y| x ^ y), checksum[0]);
+            assert_eq!(body[..2], [0x51, 0x80 | (body.len() - 2) as u8]);
+
+            let mut fake = self.0.lock().unwrap();
+            fake.requests.push(data.to_vec());
+            match body[2..] {
+                [0x01, BRIGHTNESS] => fake.reads += 1,
+                [0x03, BRIGHTNESS, high, low] => fake.current = u16::from_be_bytes([high, low]),
+                _ => panic!("unexpected request {data:x?}"),
+            }
+            Ok(())
+        }
+
+        fn read(&mut self, address: u16, buffer: &mut [u8]) -> io::Result<()> {
+            assert_eq!(address, ADDRESS);
+            let fake = self.0.lock().unwrap();
+            let [max_high, max_low] = fake.max.to_be_bytes();
+            let [high, low] = fake.current.to_be_bytes();
+            let mut reply = vec![
+                0x6e,
+                0x88,
+                0x02,
+                u8::from(fake.unsupported),
+                BRIGHTNESS,
+                0x00,
+                max_high,
+                max_low,
+                high,
+                low,
+            ];
+            let checksum = reply.iter().fold(0x50, |x, y| x ^ y);
+            reply.push(match fake.corrupt {
+                true => !checksum,
+                false => checksum,
+            });
+            buffer.copy_from_slice(&reply);
+            Ok(())
+        }
+    }
+
+    /// Buses with fake monitors on some of them.
+    pub struct FakeBuses(pub HashMap<String, Arc<Mutex<Fake>>>);
+
+    impl Buses for FakeBuses {
+        fn open(&self, bus: &str) -> io::Result<Box<dyn I2c>> {
+            match self.0.get(bus) {
+                Some(x) => Ok(Box::new(FakeMonitor(x.clone()))),
+                None => Err(io::ErrorKind::NotFound.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_and_sets_brightness() {
+        let fake = Fake::new(40, 100);
+        let mut monitor = Monitor::new(Box::new(FakeMonitor(fake.clone()))).unwrap();
+        assert_eq!(monitor.brightness(), 40);
+
+        monitor.set_brightness(70).unwrap();
+        let fake = fake.lock().unwrap();
+        assert_eq!(fake.current, 70);
+        assert_eq!(
+            fake.requests,
+            [
+                vec![0x51, 0x82, 0x01, 0x10, 0xac],
+                vec![0x51, 0x84, 0x03, 0x10, 0x00, 70, 0xee],
+            ]
+        );
+    }
+
+    #[test]
+    fn scales_to_the_monitors_maximum() {
+        let fake = Fake::new(50, 200);
+        let mut monitor = Monitor::new(Box::new(FakeMonitor(fake.clone()))).unwrap();
+        assert_eq!(monitor.brightness(), 25);
+
+        monitor.set_brightness(60).unwrap();
+        assert_eq!(fake.lock().unwrap().current, 120);
+        assert_eq!(monitor.brightness(), 60);
+
+        monitor.set_brightness(150).unwrap();
+        assert_eq!(fake.lock().unwrap().current, 200);
+    }
+
+    #[test]
+    fn brightness_is_only_asked_for_once() {
+        let fake = Fake::new(40, 100);
+        let mut monitor = Monitor::new(Box::new(FakeMonitor(fake.clone()))).unwrap();
+        monitor.set_brightness(10).unwrap();
+        assert_eq!(monitor.brightness(), 10);
+        assert_eq!(fake.lock().unwrap().reads, 1);
+    }
+
+    #[test]
+    fn rejects_bad_checksums() {
+        let fake = Fake::new(40, 100);
+        fake.lock().unwrap().corrupt = true;
+        let e = Monitor::new(Box::new(FakeMonitor(fake))).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().contains("checksum"));
+    }
+
+    #[test]
+    fn rejects_unsupported_features() {
+        let fake = Fake::new(40, 100);
+        fake.lock().unwrap().unsupported = true;
+        let e = Monitor::new(Box::new(FakeMonitor(fake))).err().unwrap();
+        assert!(e.to_string().contains("doesn't support"));
+    }
+
+    #[test]
+    fn rejects_a_maximum_of_zero() {
+        let e = Monitor::new(Box::new(FakeMonitor(Fake::new(0, 0))))
+            .err()
+            .unwrap();
+        assert!(e.to_string().contains("zero"));
+    }
+}
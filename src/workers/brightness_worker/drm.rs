@@ -0,0 +1,130 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A connected display output, as listed in `/sys/class/drm`.
+#[derive(Debug, Clone)]
+pub struct Connector {
+    /// The name sway uses for the output, such as `DP-1`.
+    pub name: String,
+    /// The connector's directory with symlinks resolved, which backlights can point at.
+    pub path: PathBuf,
+    /// The i2c bus its monitor's DDC/CI is reached through, such as `i2c-4`.
+    pub bus: Option<String>,
+}
+
+impl Connector {
+    /// Whether this is a laptop panel, which is dimmed through a backlight rather than DDC/CI.
+    pub fn is_internal(&self) -> bool {
+        ["eDP", "LVDS", "DSI"]
+            .iter()
+            .any(|x| self.name.starts_with(x))
+    }
+}
+
+/// Lists the connectors in `path` that have a display plugged in.
+pub fn connectors(path: &Path) -> Vec<Connector> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+
+    let mut connectors: Vec<Connector> = entries
+        .filter_map(|x| x.ok())
+        .filter_map(|entry| {
+            // connectors are named after their card, like card1-DP-1
+            let file_name = entry.file_name();
+            let (card, name) = file_name.to_str()?.split_once('-')?;
+            if !card.starts_with("card") {
+                return None;
+            }
+            let dir = entry.path();
+            if fs::read_to_string(dir.join("status")).ok()?.trim() != "connected" {
+                return None;
+            }
+
+            Some(Connector {
+                name: name.to_owned(),
+                path: fs::canonicalize(&dir).unwrap_or_else(|_| dir.clone()),
+                bus: bus(&dir),
+            })
+        })
+        .collect();
+    connectors.sort_by(|x, y| x.name.cmp(&y.name));
+    connectors
+}
+
+/// Finds a connector's i2c bus, which HDMI and DVI link to as `ddc`
+/// and DisplayPort lists among its children.
+fn bus(dir: &Path) -> Option<String> {
+    if let Ok(x) = fs::read_link(dir.join("ddc")) {
+        return Some(x.file_name()?.to_str()?.to_owned());
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|x| x.ok())
+        .filter_map(|x| x.file_name().into_string().ok())
+        .find(|x| x.starts_with("i2c-"))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    /// Adds a connector the way the kernel lays them out, with `/sys/class/drm`'s entry
+    /// linking to the card's device directory, and returns the entry.
+    pub fn add_connector(dir: &Path, name: &str, status: &str, bus: Option<&str>) -> PathBuf {
+        let device = dir.join("devices/card1").join(format!("card1-{name}"));
+        fs::create_dir_all(&device).unwrap();
+        fs::write(device.join("status"), format!("{status}\n")).unwrap();
+        match bus {
+            // DisplayPort's AUX channel is its own bus, listed among the connector's children
+            Some(x) if name.starts_with("DP") => fs::create_dir_all(device.join(x)).unwrap(),
+            Some(x) => symlink(dir.join("devices/i2c").join(x), device.join("ddc")).unwrap(),
+            None => {}
+        }
+
+        let class = dir.join("drm");
+        fs::create_dir_all(&class).unwrap();
+        let entry = class.join(format!("card1-{name}"));
+        symlink(&device, &entry).unwrap();
+        entry
+    }
+
+    #[test]
+    fn lists_connected_connectors_and_their_buses() {
+        let dir = std::env::temp_dir().join(format!("swirly-drm-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        add_connector(&dir, "HDMI-A-1", "connected", Some("i2c-3"));
+        add_connector(&dir, "DP-2", "connected", Some("i2c-7"));
+        add_connector(&dir, "DP-1", "disconnected", Some("i2c-6"));
+        add_connector(&dir, "eDP-1", "connected", None);
+        // cards themselves sit alongside their connectors
+        fs::create_dir_all(dir.join("drm/card1")).unwrap();
+        fs::create_dir_all(dir.join("drm/renderD128")).unwrap();
+
+        let found = connectors(&dir.join("drm"));
+        let names: Vec<(&str, Option<&str>, bool)> = found
+            .iter()
+            .map(|x| (x.name.as_str(), x.bus.as_deref(), x.is_internal()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("DP-2", Some("i2c-7"), false),
+                ("HDMI-A-1", Some("i2c-3"), false),
+                ("eDP-1", None, true),
+            ]
+        );
+        // backlights point at the device directory rather than the class entry
+        assert_eq!(
+            found[2].path,
+            fs::canonicalize(dir.join("devices/card1/card1-eDP-1")).unwrap()
+        );
+
+        assert!(connectors(&dir.join("missing")).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
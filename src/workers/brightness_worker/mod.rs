@@ -0,0 +1,799 @@
+mod backend;
+mod backlight;
+pub mod ddc;
+mod drm;
+mod uevent;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use notify::{RecursiveMode, Watcher};
+use relm4::{ComponentSender, Worker};
+
+use crate::config::{BrightnessBackendKind, BrightnessConfig};
+use crate::ipc::Response;
+use backend::{BrightnessBackend, Device, Error};
+
+/// The shortest time between two writes to a display, however fast the slider moves.
+const WRITE_INTERVAL: Duration = Duration::from_millis(30);
+
+pub struct AsyncHandler {
+    displays: Displays,
+    /// How long changes take to fade in by default.
+    fade: Duration,
+    /// Where each display's brightness is headed, keyed by id.
+    ///
+    /// Requests only land here, and each tick writes whatever is latest,
+    /// so a slider being dragged doesn't queue up a write per step.
+    pending: HashMap<String, Transition>,
+    /// Asks the ticker thread for an [`Input::Tick`] after a delay.
+    ticker: mpsc::Sender<Duration>,
+    ticking: bool,
+    last_write: Option<Instant>,
+    // the last values reported, so repeated checks don't cause updates
+    reported: Option<Vec<Display>>,
+    brightness: Option<u32>,
+}
+
+/// A change in brightness being faded in.
+struct Transition {
+    from: u32,
+    to: u32,
+    start: Instant,
+    duration: Duration,
+    /// The last value written, to skip writes a slow fade doesn't need.
+    written: Option<u32>,
+    /// Whoever asked for the change, answered once the first step is written.
+    reply: Option<flume::Sender<Response>>,
+}
+
+impl Transition {
+    fn current(&self, now: Instant) -> u32 {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return self.to;
+        }
+        let progress = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+        (self.from as f64 + (self.to as f64 - self.from as f64) * progress).round() as u32
+    }
+
+    fn is_done(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+}
+
+/// Every backlight and DDC/CI monitor, kept apart from the worker so it can be tested
+/// against fake sysfs directories and i2c buses.
+pub struct Displays {
+    /// The backends in the order they're tried.
+    kinds: Vec<BrightnessBackendKind>,
+    backends: Vec<Box<dyn BrightnessBackend>>,
+    ddc: bool,
+    /// The directory backlights are listed in, normally `/sys/class/backlight`.
+    backlight_path: PathBuf,
+    /// The directory display connectors are listed in, normally `/sys/class/drm`.
+    drm_path: PathBuf,
+    buses: Box<dyn ddc::Buses>,
+    backlights: Vec<Entry>,
+    monitors: Vec<Entry>,
+    /// Buses found not to speak DDC/CI, which aren't asked again while their monitor stays connected.
+    unsupported: HashSet<String>,
+}
+
+struct Entry {
+    display: Display,
+    device: Device,
+    /// The index of the backend that last worked for the display.
+    backend: Option<usize>,
+}
+
+/// A screen whose brightness can be set.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Display {
+    /// Identifies the display when setting its brightness, such as `backlight/intel_backlight`.
+    pub id: String,
+    /// The sway output showing it, or failing that the device's name.
+    pub name: String,
+    /// Percentage of the maximum brightness.
+    pub brightness: u32,
+}
+
+#[derive(Debug)]
+pub enum Input {
+    /// Looks for displays again, reading the backlights and any monitors not seen before.
+    Refresh,
+    /// Only reads the backlights, which are cheap to read unlike monitors.
+    RefreshBacklights,
+    /// Sets the brightness of the main display, the first backlight or else the first monitor.
+    ///
+    /// The change fades in over the given time, or the configured one if there isn't one,
+    /// and the channel is answered once it starts.
+    SetBrightness(u32, Option<Duration>, Option<flume::Sender<Response>>),
+    SetDisplayBrightness(String, u32),
+    /// Writes the next step of every pending change.
+    Tick,
+    /// Only the paths are fixed at startup.
+    UpdateConfig(BrightnessConfig),
+}
+
+#[derive(Debug)]
+pub enum Output {
+    /// The brightness of the main display.
+    UpdateBrightness(u32),
+    UpdateDisplays(Vec<Display>),
+}
+
+impl Worker for AsyncHandler {
+    type Init = BrightnessConfig;
+    type Input = Input;
+    type Output = Output;
+
+    fn init(config: Self::Init, sender: ComponentSender<Self>) -> Self {
+        std::thread::spawn({
+            let path = config.path.clone();
+            let interval = Duration::from_secs(config.interval);
+            let sender = sender.input_sender().clone();
+            move || watch(&path, interval, &sender)
+        });
+        sender.input(Input::Refresh);
+
+        let (ticker, delays) = mpsc::channel();
+        std::thread::spawn({
+            let sender = sender.input_sender().clone();
+            move || {
+                while let Ok(x) = delays.recv() {
+                    std::thread::sleep(x);
+                    if sender.send(Input::Tick).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        let buses = Box::new(ddc::DevBuses(config.i2c_path.clone()));
+        Self {
+            fade: Duration::from_millis(config.fade),
+            displays: Displays::new(config, buses),
+            pending: HashMap::new(),
+            ticker,
+            ticking: false,
+            last_write: None,
+            reported: None,
+            brightness: None,
+        }
+    }
+
+    fn update(&mut self, msg: Input, sender: ComponentSender<Self>) {
+        match msg {
+            Input::Refresh => self.displays.refresh(),
+            Input::RefreshBacklights => self.displays.refresh_backlights(),
+            Input::SetBrightness(x, fade, reply) => {
+                let Some(id) = self.displays.main().map(|x| x.id.clone()) else {
+                    let message = "Failed to set brightness: no display found.";
+                    match reply {
+                        Some(reply) => {
+                            let _ = reply.send(Response::error(message));
+                        }
+                        None => log::error!("{message}"),
+                    }
+                    return;
+                };
+                self.request(id, x, fade.unwrap_or(self.fade), reply);
+            }
+            Input::SetDisplayBrightness(id, x) => self.request(id, x, self.fade, None),
+            Input::Tick => self.tick(),
+            Input::UpdateConfig(x) => {
+                self.fade = Duration::from_millis(x.fade);
+                self.displays.update_config(&x);
+            }
+        }
+
+        if let Some(x) = self.displays.main().map(|x| x.brightness) {
+            if self.brightness != Some(x) {
+                self.brightness = Some(x);
+                sender.output(Output::UpdateBrightness(x)).unwrap();
+            }
+        }
+        let displays = self.displays.list();
+        if self.reported.as_ref() != Some(&displays) {
+            self.reported = Some(displays.clone());
+            sender.output(Output::UpdateDisplays(displays)).unwrap();
+        }
+    }
+}
+
+impl AsyncHandler {
+    /// Queues a change to a display's brightness for the next tick.
+    fn request(
+        &mut self,
+        id: String,
+        brightness: u32,
+        fade: Duration,
+        reply: Option<flume::Sender<Response>>,
+    ) {
+        let now = Instant::now();
+        // a change made mid-fade carries on from wherever the fade got to
+        let from = match self.pending.get(&id) {
+            Some(x) => x.current(now),
+            None => match self.displays.get(&id) {
+                Some(x) => x.brightness,
+                None => brightness,
+            },
+        };
+        let replaced = self.pending.insert(
+            id,
+            Transition {
+                from,
+                to: brightness.min(100),
+                start: now,
+                duration: fade,
+                written: None,
+                reply,
+            },
+        );
+        if let Some(reply) = replaced.and_then(|x| x.reply) {
+            let _ = reply.send(Response::Ok);
+        }
+
+        if !self.ticking {
+            self.ticking = true;
+            let delay = self.last_write.map_or(Duration::ZERO, |x| {
+                WRITE_INTERVAL.saturating_sub(x.elapsed())
+            });
+            let _ = self.ticker.send(delay);
+        }
+    }
+
+    fn tick(&mut self) {
+        self.ticking = false;
+        let now = Instant::now();
+
+        for (id, transition) in self.pending.iter_mut() {
+            let brightness = transition.current(now);
+            if transition.written == Some(brightness) {
+                continue;
+            }
+            transition.written = Some(brightness);
+
+            let result = self.displays.set_brightness(id, brightness);
+            match (result, transition.reply.take()) {
+                (Ok(()), Some(reply)) => {
+                    let _ = reply.send(Response::Ok);
+                }
+                (Err(e), Some(reply)) => {
+                    let _ = reply.send(Response::error(format!("Failed to set brightness: {e}")));
+                }
+                (Ok(()), None) => (),
+                (Err(e), None) => log::error!("Failed to set brightness of {id}: {e}"),
+            }
+        }
+        self.last_write = Some(Instant::now());
+
+        self.pending.retain(|_, x| !x.is_done(now));
+        if !self.pending.is_empty() {
+            self.ticking = true;
+            let _ = self.ticker.send(WRITE_INTERVAL);
+        }
+    }
+}
+
+impl Displays {
+    /// Looks for displays in the sysfs directories in `config`, reaching monitors through
+    /// `buses`. Nothing is found until the first refresh.
+    pub fn new(config: BrightnessConfig, buses: Box<dyn ddc::Buses>) -> Self {
+        Self {
+            backends: config.backends.iter().copied().map(backend::new).collect(),
+            kinds: config.backends,
+            ddc: config.ddc,
+            backlight_path: config.path,
+            drm_path: config.drm_path,
+            buses,
+            backlights: Vec::new(),
+            monitors: Vec::new(),
+            unsupported: HashSet::new(),
+        }
+    }
+
+    /// Returns the backlights followed by the monitors.
+    pub fn list(&self) -> Vec<Display> {
+        self.backlights
+            .iter()
+            .chain(&self.monitors)
+            .map(|x| x.display.clone())
+            .collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Display> {
+        self.backlights
+            .iter()
+            .chain(&self.monitors)
+            .map(|x| &x.display)
+            .find(|x| x.id == id)
+    }
+
+    /// Returns the display the bar shows, the first backlight or else the first monitor.
+    pub fn main(&self) -> Option<&Display> {
+        self.backlights
+            .first()
+            .or(self.monitors.first())
+            .map(|x| &x.display)
+    }
+
+    pub fn update_config(&mut self, config: &BrightnessConfig) {
+        if self.kinds != config.backends {
+            self.kinds = config.backends.clone();
+            self.backends = self.kinds.iter().copied().map(backend::new).collect();
+            for x in self.backlights.iter_mut().chain(&mut self.monitors) {
+                x.backend = None;
+            }
+        }
+        if self.ddc != config.ddc {
+            self.ddc = config.ddc;
+            self.refresh_monitors();
+        }
+    }
+
+    /// Looks for displays again, reading the backlights and any monitors not seen before.
+    pub fn refresh(&mut self) {
+        self.refresh_backlights();
+        self.refresh_monitors();
+    }
+
+    pub fn refresh_backlights(&mut self) {
+        let connectors = drm::connectors(&self.drm_path);
+        // laptops often have a single panel whose backlight isn't linked to it
+        let internal: Vec<&drm::Connector> =
+            connectors.iter().filter(|x| x.is_internal()).collect();
+
+        let previous = std::mem::take(&mut self.backlights);
+        self.backlights = backlight::list(&self.backlight_path)
+            .into_iter()
+            .filter_map(|path| {
+                let brightness = match backlight::read(&path) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        log::warn!("Failed to read brightness of {}: {e}", path.display());
+                        return None;
+                    }
+                };
+                let device = std::fs::canonicalize(path.join("device")).ok();
+                let name = connectors
+                    .iter()
+                    .find(|x| Some(&x.path) == device.as_ref())
+                    .or(match internal[..] {
+                        [x] => Some(x),
+                        _ => None,
+                    })
+                    .map_or_else(|| file_name(&path), |x| x.name.clone());
+
+                let id = format!("backlight/{}", file_name(&path));
+                Some(Entry {
+                    // stick with what worked, which may not be the first backend that looks able
+                    backend: previous
+                        .iter()
+                        .find(|x| x.display.id == id)
+                        .and_then(|x| x.backend),
+                    display: Display {
+                        id,
+                        name,
+                        brightness,
+                    },
+                    device: Device::Backlight(path),
+                })
+            })
+            .collect();
+    }
+
+    fn refresh_monitors(&mut self) {
+        if !self.ddc {
+            self.monitors.clear();
+            return;
+        }
+
+        let connectors: Vec<(String, String)> = drm::connectors(&self.drm_path)
+            .into_iter()
+            .filter(|x| !x.is_internal())
+            .filter_map(|x| Some((x.bus?, x.name)))
+            .collect();
+        // a different monitor may be plugged in next time
+        self.unsupported
+            .retain(|x| connectors.iter().any(|(bus, _)| bus == x));
+
+        let mut monitors = std::mem::take(&mut self.monitors);
+        for (bus, name) in connectors {
+            if self.unsupported.contains(&bus) {
+                continue;
+            }
+            let id = format!("ddc/{bus}");
+
+            // monitors already found keep their brightness rather than being asked again
+            let entry = match monitors.iter().position(|x| x.display.id == id) {
+                Some(i) => monitors.swap_remove(i),
+                None => match self.buses.open(&bus).and_then(ddc::Monitor::new) {
+                    Ok(x) => Entry {
+                        display: Display {
+                            id,
+                            name,
+                            brightness: x.brightness(),
+                        },
+                        device: Device::Monitor(x),
+                        backend: None,
+                    },
+                    Err(e) => {
+                        log::info!("Not controlling brightness of {name} through {bus}: {e}");
+                        self.unsupported.insert(bus);
+                        continue;
+                    }
+                },
+            };
+            self.monitors.push(entry);
+        }
+    }
+
+    /// Sets a display's brightness through the backend that last worked for it,
+    /// moving on to the others in order if that fails.
+    pub fn set_brightness(&mut self, id: &str, brightness: u32) -> Result<(), Error> {
+        let entry = self
+            .backlights
+            .iter_mut()
+            .chain(&mut self.monitors)
+            .find(|x| x.display.id == id)
+            .ok_or_else(|| format!("no display called {id}"))?;
+
+        let mut failed = Vec::new();
+        loop {
+            let index = match entry.backend {
+                Some(x) => x,
+                None => {
+                    let Some(x) = (0..self.backends.len())
+                        .filter(|x| !failed.contains(x))
+                        .find(|x| self.backends[*x].probe(&entry.device))
+                    else {
+                        return Err(match failed.is_empty() {
+                            true => format!("no backend can control {}", entry.display.name),
+                            false => format!("every backend failed for {}", entry.display.name),
+                        }
+                        .into());
+                    };
+                    log::info!(
+                        "Setting brightness of {} through {}.",
+                        entry.display.name,
+                        self.backends[x].name()
+                    );
+                    entry.backend = Some(x);
+                    x
+                }
+            };
+
+            match self.backends[index].set(&mut entry.device, brightness) {
+                Ok(()) => {
+                    // monitors don't announce changes, so report it right away
+                    if matches!(entry.device, Device::Monitor(_)) {
+                        entry.display.brightness = brightness;
+                    }
+                    return Ok(());
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Failed to set brightness of {} through {}: {e}",
+                        entry.display.name,
+                        self.backends[index].name()
+                    );
+                    entry.backend = None;
+                    failed.push(index);
+                }
+            }
+        }
+    }
+}
+
+/// Something [`watch`] noticed.
+enum Change {
+    Backlights,
+    /// A display connector, so a monitor may have been plugged in or out.
+    Connectors,
+    /// Uevents stopped arriving, so changes have to be looked for every interval.
+    Lost,
+}
+
+/// Sends [`Input::RefreshBacklights`] whenever a backlight in `path` changes
+/// and [`Input::Refresh`] whenever a monitor may have been plugged in or out.
+///
+/// Changes are announced by uevents, or failing that looked for every `interval`.
+fn watch(path: &Path, interval: Duration, sender: &relm4::Sender<Input>) {
+    let (tx, rx) = mpsc::channel();
+    let mut uevents = match uevent::Socket::open() {
+        Ok(socket) => {
+            std::thread::spawn({
+                let tx = tx.clone();
+                move || listen(&socket, &tx)
+            });
+            true
+        }
+        Err(e) => {
+            log::warn!("Failed to listen for uevents, checking brightness every interval: {e}");
+            false
+        }
+    };
+
+    // uevents only come from real devices, so a fake sysfs is watched with inotify
+    let mut watcher = match notify::recommended_watcher({
+        let tx = tx.clone();
+        move |_| {
+            let _ = tx.send(Change::Backlights);
+        }
+    }) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            log::error!("Failed to create brightness watcher: {e}");
+            None
+        }
+    };
+    drop(tx);
+    if let Some(watcher) = watcher.as_mut() {
+        if let Err(e) = watcher.watch(path, RecursiveMode::NonRecursive) {
+            log::warn!("Failed to watch {}: {e}", path.display());
+        }
+    }
+
+    let mut watched = Vec::new();
+    loop {
+        let devices = backlight::list(path);
+        if let Some(watcher) = watcher.as_mut() {
+            for x in watched.iter().filter(|x| !devices.contains(x)) {
+                // the file may already be gone along with the device
+                let _ = watcher.unwatch(&x.join("brightness"));
+            }
+            for x in devices.iter().filter(|x| !watched.contains(x)) {
+                let file = x.join("brightness");
+                if let Err(e) = watcher.watch(&file, RecursiveMode::NonRecursive) {
+                    log::warn!("Failed to watch {}: {e}", file.display());
+                }
+            }
+        }
+        watched = devices;
+
+        let change = match uevents {
+            true => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            false => rx.recv_timeout(interval),
+        };
+        let mut input = match change {
+            Ok(Change::Backlights) => Input::RefreshBacklights,
+            Ok(Change::Connectors) => Input::Refresh,
+            Ok(Change::Lost) => {
+                uevents = false;
+                Input::Refresh
+            }
+            Err(RecvTimeoutError::Timeout) => Input::Refresh,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        // one read covers however many changes happened in the meantime
+        while let Ok(x) = rx.try_recv() {
+            match x {
+                Change::Backlights => (),
+                Change::Connectors => input = Input::Refresh,
+                Change::Lost => {
+                    uevents = false;
+                    input = Input::Refresh;
+                }
+            }
+        }
+        if sender.send(input).is_err() {
+            return;
+        }
+    }
+}
+
+/// Passes on uevents about backlights and display connectors.
+fn listen(socket: &uevent::Socket, tx: &mpsc::Sender<Change>) {
+    loop {
+        let change = match socket.recv() {
+            Ok(message) => match uevent::subsystem(&message) {
+                Some("backlight") => Change::Backlights,
+                Some("drm") => Change::Connectors,
+                _ => continue,
+            },
+            // whatever was missed is read again
+            Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => Change::Connectors,
+            Err(e) => {
+                log::error!("Failed to read uevents, checking brightness every interval: {e}");
+                let _ = tx.send(Change::Lost);
+                return;
+            }
+        };
+        if tx.send(change).is_err() {
+            return;
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::symlink, thread};
+
+    use super::*;
+    use ddc::tests::{Fake, FakeBuses};
+    use drm::tests::add_connector;
+
+    /// An empty directory of the test's own.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("swirly-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn add_backlight(dir: &Path, name: &str, brightness: u32, max: u32) -> PathBuf {
+        let device = dir.join(name);
+        fs::create_dir_all(&device).unwrap();
+        fs::write(device.join("brightness"), brightness.to_string()).unwrap();
+        fs::write(device.join("max_brightness"), max.to_string()).unwrap();
+        device
+    }
+
+    fn config(dir: &Path) -> BrightnessConfig {
+        BrightnessConfig {
+            path: dir.join("backlight"),
+            drm_path: dir.join("drm"),
+            i2c_path: dir.join("dev"),
+            ddc: false,
+            ..BrightnessConfig::default()
+        }
+    }
+
+    fn brightness(displays: &Displays) -> Vec<(String, u32)> {
+        displays
+            .list()
+            .into_iter()
+            .map(|x| (x.id, x.brightness))
+            .collect()
+    }
+
+    #[test]
+    fn watch_reports_backlight_changes() {
+        let dir = temp_dir("watch");
+        let backlights = dir.join("backlight");
+        let device = add_backlight(&backlights, "acpi_video0", 50, 200);
+
+        let mut displays = Displays::new(config(&dir), Box::new(ddc::DevBuses(dir.join("dev"))));
+        displays.refresh();
+        assert_eq!(
+            brightness(&displays),
+            [("backlight/acpi_video0".to_string(), 25)]
+        );
+
+        // the interval is long enough that only watching can explain an update
+        let (sender, receiver) = relm4::channel();
+        thread::spawn({
+            let backlights = backlights.clone();
+            move || watch(&backlights, Duration::from_secs(600), &sender)
+        });
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            while let Some(x) = receiver.recv_sync() {
+                if tx.send(x).is_err() {
+                    return;
+                }
+            }
+        });
+        // lets the watches be set up before anything changes
+        thread::sleep(Duration::from_millis(200));
+
+        let next = |displays: &mut Displays| {
+            match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+                Input::RefreshBacklights => displays.refresh_backlights(),
+                Input::Refresh => displays.refresh(),
+                x => panic!("unexpected {x:?}"),
+            }
+            // one change can be announced more than once
+            thread::sleep(Duration::from_millis(100));
+            while rx.try_recv().is_ok() {}
+        };
+
+        fs::write(device.join("brightness"), "150").unwrap();
+        next(&mut displays);
+        assert_eq!(
+            brightness(&displays),
+            [("backlight/acpi_video0".to_string(), 75)]
+        );
+
+        fs::remove_dir_all(&device).unwrap();
+        next(&mut displays);
+        assert!(brightness(&displays).is_empty());
+
+        add_backlight(&backlights, "acpi_video0", 20, 200);
+        next(&mut displays);
+        assert_eq!(
+            brightness(&displays),
+            [("backlight/acpi_video0".to_string(), 10)]
+        );
+
+        // the recreated device is watched too
+        fs::write(device.join("brightness"), "200").unwrap();
+        next(&mut displays);
+        assert_eq!(
+            brightness(&displays),
+            [("backlight/acpi_video0".to_string(), 100)]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn names_displays_after_their_outputs() {
+        let dir = temp_dir("outputs");
+        add_connector(&dir, "eDP-1", "connected", None);
+        let panel = add_connector(&dir, "eDP-2", "connected", None);
+        add_connector(&dir, "DP-1", "connected", Some("i2c-5"));
+        add_connector(&dir, "HDMI-A-1", "connected", Some("i2c-3"));
+        add_connector(&dir, "DP-2", "disconnected", Some("i2c-6"));
+        // with two panels, only the link tells which one the backlight belongs to
+        let backlight = add_backlight(&dir.join("backlight"), "intel_backlight", 300, 600);
+        symlink(&panel, backlight.join("device")).unwrap();
+
+        // the HDMI monitor doesn't answer and DP-2's isn't plugged in
+        let monitor = Fake::new(30, 100);
+        let unplugged = Fake::new(80, 100);
+        let buses = FakeBuses(
+            [
+                ("i2c-5".to_string(), monitor.clone()),
+                ("i2c-6".to_string(), unplugged.clone()),
+            ]
+            .into(),
+        );
+        let mut displays = Displays::new(
+            BrightnessConfig {
+                ddc: true,
+                ..config(&dir)
+            },
+            Box::new(buses),
+        );
+        displays.refresh();
+
+        let names: Vec<(String, String, u32)> = displays
+            .list()
+            .into_iter()
+            .map(|x| (x.id, x.name, x.brightness))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (
+                    "backlight/intel_backlight".to_string(),
+                    "eDP-2".to_string(),
+                    50
+                ),
+                ("ddc/i2c-5".to_string(), "DP-1".to_string(), 30),
+            ]
+        );
+        assert_eq!(unplugged.lock().unwrap().reads, 0);
+
+        displays.set_brightness("ddc/i2c-5", 60).unwrap();
+        assert_eq!(monitor.lock().unwrap().current, 60);
+        assert_eq!(displays.get("ddc/i2c-5").unwrap().brightness, 60);
+
+        // refreshing only reads sysfs, trusting the monitor to keep what was set
+        fs::write(backlight.join("brightness"), "600").unwrap();
+        displays.refresh();
+        assert_eq!(
+            brightness(&displays),
+            [
+                ("backlight/intel_backlight".to_string(), 100),
+                ("ddc/i2c-5".to_string(), 60),
+            ]
+        );
+        assert_eq!(monitor.lock().unwrap().reads, 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+/// `NETLINK_KOBJECT_UEVENT`'s group for messages straight from the kernel,
+/// rather than udev passing them on after it's done with them.
+const KERNEL_GROUP: u32 = 1;
+
+/// The kernel's announcements of devices changing, coming and going.
+///
+/// Backlights announce every change here, including ones made by the firmware
+/// when brightness keys are pressed, which inotify never sees.
+pub struct Socket(OwnedFd);
+
+impl Socket {
+    pub fn open() -> io::Result<Self> {
+        // SAFETY: plain socket creation, the descriptor is owned right after
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_KOBJECT_UEVENT,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the descriptor was just created and nothing else owns it
+        let socket = Self(unsafe { OwnedFd::from_raw_fd(fd) });
+
+        // SAFETY: sockaddr_nl is plain data, all zeroes is a valid value
+        let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups = KERNEL_GROUP;
+        // SAFETY: the address is a sockaddr_nl of the size given
+        let result = unsafe {
+            libc::bind(
+                socket.0.as_raw_fd(),
+                (&address as *const libc::sockaddr_nl).cast(),
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(socket)
+    }
+
+    /// Waits for the next message.
+    ///
+    /// Fails with `ENOBUFS` if messages came faster than they were read and some were lost.
+    pub fn recv(&self) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0; 8192];
+        loop {
+            // SAFETY: the buffer is writable for its whole length
+            let length = unsafe {
+                libc::recv(
+                    self.0.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    0,
+                )
+            };
+            if length < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e);
+            }
+            buffer.truncate(length as usize);
+            return Ok(buffer);
+        }
+    }
+}
+
+/// Returns the subsystem of the device a uevent is about, such as `backlight` or `drm`.
+///
+/// Messages are an `action@devpath` header followed by `KEY=value` pairs, all ending in nul.
+pub fn subsystem(message: &[u8]) -> Option<&str> {
+    let mut fields = message
+        .split(|x| *x == 0)
+        .filter_map(|x| std::str::from_utf8(x).ok());
+    // udev's own messages start with "libudev" instead
+    if !fields.next()?.contains('@') {
+        return None;
+    }
+    fields.find_map(|x| x.strip_prefix("SUBSYSTEM="))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_subsystem() {
+        let message =
+            b"change@/devices/pci0000:00/0000:00:02.0/drm/card1/card1-eDP-1/intel_backlight\0\
+            ACTION=change\0\
+            DEVPATH=/devices/pci0000:00/0000:00:02.0/drm/card1/card1-eDP-1/intel_backlight\0\
+            SUBSYSTEM=backlight\0\
+            SOURCE=hotkey\0\
+            SEQNUM=4242\0";
+        assert_eq!(subsystem(message), Some("backlight"));
+
+        let message = b"change@/devices/pci0000:00/0000:00:02.0/drm/card1\0\
+            ACTION=change\0\
+            SUBSYSTEM=drm\0\
+            HOTPLUG=1\0";
+        assert_eq!(subsystem(message), Some("drm"));
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        assert_eq!(subsystem(b"libudev\0\xfe\xed\xca\xfe"), None);
+        assert_eq!(
+            subsystem(b"add@/devices/virtual/misc/foo\0ACTION=add\0"),
+            None
+        );
+        assert_eq!(subsystem(b""), None);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use relm4::{ComponentSender, Worker};
+
+use crate::config::{self, Config};
+use crate::style;
+
+pub struct AsyncHandler {
+    dir: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum Input {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum Output {
+    UpdateConfig(Box<Config>),
+    UpdateCSS,
+}
+
+impl Worker for AsyncHandler {
+    type Init = ();
+    type Input = Input;
+    type Output = Output;
+
+    fn init(_init: Self::Init, sender: ComponentSender<Self>) -> Self {
+        sender.input(Input::Start);
+        Self { dir: config::dir() }
+    }
+
+    fn update(&mut self, msg: Input, sender: ComponentSender<Self>) {
+        // editors tend to write a file in several steps,
+        // so wait for things to settle before reparsing
+        let settle = Duration::from_millis(100);
+        match msg {
+            Input::Start => {
+                let Some(dir) = self.dir.as_ref() else {
+                    log::error!("Failed to find config directory, not watching for changes.");
+                    return;
+                };
+
+                let (tx, rx) = mpsc::channel();
+                let mut watcher = match notify::recommended_watcher(tx) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        log::error!("Failed to create config watcher: {e}");
+                        return;
+                    }
+                };
+                let path = dir.join(config::FILE_NAME);
+                let css_path = dir.join(style::FILE_NAME);
+
+                // the directory may only be made once swirly is running,
+                // so its parent is watched until it shows up
+                let created = !dir.exists();
+                if created {
+                    let Some(parent) = dir.parent() else {
+                        return;
+                    };
+                    if let Err(e) = watcher.watch(parent, RecursiveMode::NonRecursive) {
+                        log::warn!(
+                            "Failed to watch {}, config changes need a restart: {e}",
+                            parent.display()
+                        );
+                        return;
+                    }
+                    log::info!("Waiting for {} to be created.", dir.display());
+                    while !dir.exists() {
+                        if rx.recv().is_err() {
+                            return;
+                        }
+                    }
+                    let _ = watcher.unwatch(parent);
+                }
+
+                // watching the directory rather than the file
+                // keeps working when editors replace the file
+                if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    log::warn!(
+                        "Failed to watch {}, config changes need a restart: {e}",
+                        dir.display()
+                    );
+                    return;
+                }
+                // files written before the watch started would be missed otherwise
+                if created {
+                    std::thread::sleep(settle);
+                    if css_path.exists() {
+                        sender.output(Output::UpdateCSS).unwrap();
+                    }
+                    if path.exists() {
+                        reload(&path, &sender);
+                    }
+                }
+
+                while let Ok(event) = rx.recv() {
+                    let mut changed = is_change(&event, &path);
+                    let mut css_changed = is_change(&event, &css_path);
+                    while let Ok(event) = rx.recv_timeout(settle) {
+                        changed |= is_change(&event, &path);
+                        css_changed |= is_change(&event, &css_path);
+                    }
+
+                    if css_changed {
+                        sender.output(Output::UpdateCSS).unwrap();
+                    }
+                    if !changed || !path.exists() {
+                        continue;
+                    }
+
+                    reload(&path, &sender);
+                }
+            }
+        }
+    }
+}
+
+fn reload(path: &Path, sender: &ComponentSender<AsyncHandler>) {
+    match config::load_from(path) {
+        Ok(x) => {
+            log::info!("Reloaded {}.", path.display());
+            sender.output(Output::UpdateConfig(Box::new(x))).unwrap();
+        }
+        Err(e) => {
+            log::error!("Failed to reload config, keeping the old one: {e}");
+        }
+    }
+}
+
+fn is_change(event: &notify::Result<notify::Event>, path: &Path) -> bool {
+    match event {
+        Ok(x) => !matches!(x.kind, EventKind::Access(_)) && x.paths.iter().any(|x| x == path),
+        Err(e) => {
+            log::warn!("Error while watching config: {e}");
+            false
+        }
+    }
+}
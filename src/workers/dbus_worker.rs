@@ -0,0 +1,63 @@
+use relm4::{ComponentSender, Worker};
+
+use crate::dbus;
+use crate::ipc::{Request, Response, State};
+
+pub struct AsyncHandler {
+    service: Option<dbus::Service>,
+}
+
+#[derive(Debug)]
+pub enum Input {
+    UpdateState(State),
+}
+
+#[derive(Debug)]
+pub enum Output {
+    Request(Request, flume::Sender<Response>),
+}
+
+impl Worker for AsyncHandler {
+    type Init = ();
+    type Input = Input;
+    type Output = Output;
+
+    fn init(_init: Self::Init, sender: ComponentSender<Self>) -> Self {
+        let output = sender.output_sender().clone();
+        let handler: dbus::Handler = Box::new(move |request| {
+            let (tx, rx) = flume::bounded(1);
+            let sent = output.send(Output::Request(request, tx)).is_ok();
+            // callers give up on their own after D-Bus's method call timeout
+            Box::pin(async move {
+                if !sent {
+                    return Response::error("Swirly is shutting down.");
+                }
+                rx.recv_async()
+                    .await
+                    .unwrap_or_else(|_| Response::error("Swirly dropped the request."))
+            })
+        });
+
+        let service = match dbus::Service::session(handler) {
+            Ok(x) => Some(x),
+            Err(e) => {
+                log::error!("Failed to register {} on the session bus: {e}", dbus::NAME);
+                None
+            }
+        };
+
+        Self { service }
+    }
+
+    fn update(&mut self, msg: Input, _sender: ComponentSender<Self>) {
+        match msg {
+            Input::UpdateState(x) => {
+                if let Some(service) = &self.service {
+                    if let Err(e) = service.update(x) {
+                        log::warn!("Failed to send D-Bus property changes: {e}");
+                    }
+                }
+            }
+        }
+    }
+}
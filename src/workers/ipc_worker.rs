@@ -0,0 +1,122 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    time::Duration,
+};
+
+use relm4::{ComponentSender, Worker};
+
+use crate::ipc::{self, Request, Response};
+
+pub struct AsyncHandler;
+
+#[derive(Debug)]
+pub enum Input {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum Output {
+    Request(Request, flume::Sender<Response>),
+}
+
+impl Worker for AsyncHandler {
+    type Init = ();
+    type Input = Input;
+    type Output = Output;
+
+    fn init(_init: Self::Init, sender: ComponentSender<Self>) -> Self {
+        sender.input(Input::Start);
+        Self
+    }
+
+    fn update(&mut self, msg: Input, sender: ComponentSender<Self>) {
+        match msg {
+            Input::Start => {
+                let Some(path) = ipc::socket_path() else {
+                    log::error!("Failed to find XDG_RUNTIME_DIR, not listening for commands.");
+                    return;
+                };
+
+                if path.exists() {
+                    if UnixStream::connect(&path).is_ok() {
+                        log::error!(
+                            "Another instance is already listening on {}.",
+                            path.display()
+                        );
+                        return;
+                    }
+                    // left behind by a previous instance which didn't exit cleanly
+                    if let Err(e) = fs::remove_file(&path) {
+                        log::error!("Failed to remove stale socket {}: {e}", path.display());
+                        return;
+                    }
+                }
+
+                let listener = match UnixListener::bind(&path) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        log::error!("Failed to listen on {}: {e}", path.display());
+                        return;
+                    }
+                };
+
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let sender = sender.clone();
+                            std::thread::spawn(move || handle_client(stream, &sender));
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to accept connection: {e}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn handle_client(stream: UnixStream, sender: &ComponentSender<AsyncHandler>) {
+    let timeout = Duration::from_secs(1);
+    let mut writer = match stream.try_clone() {
+        Ok(x) => x,
+        Err(e) => {
+            log::warn!("Failed to set up connection: {e}");
+            return;
+        }
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(x) => x,
+            Err(e) => {
+                log::warn!("Failed to read request: {e}");
+                return;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => {
+                let (tx, rx) = flume::bounded(1);
+                if sender.output(Output::Request(request, tx)).is_err() {
+                    return;
+                }
+                rx.recv_timeout(timeout)
+                    .unwrap_or_else(|_| Response::error("Timed out waiting for a reply."))
+            }
+            Err(e) => Response::error(format!("Invalid request: {e}")),
+        };
+
+        let mut buf = serde_json::to_string(&response).unwrap();
+        buf.push('\n');
+        if let Err(e) = writer.write_all(buf.as_bytes()) {
+            log::warn!("Failed to send response: {e}");
+            return;
+        }
+    }
+}
@@ -1,6 +1,9 @@
 pub mod audio_worker;
 pub mod battery_worker;
 pub mod brightness_worker;
+pub mod config_worker;
+pub mod dbus_worker;
+pub mod ipc_worker;
 pub mod sway_executor;
 pub mod sway_worker;
 pub mod time_worker;
@@ -1,4 +1,4 @@
-use swayipc::{Connection, Event, EventType, WindowEvent};
+use swayipc::{Connection, Event, EventType, WindowEvent, Workspace, WorkspaceChange};
 
 use relm4::{ComponentSender, Worker};
 
@@ -11,8 +11,10 @@ pub enum Input {
 
 #[derive(Debug)]
 pub enum Output {
-    UpdateWorkspaces(i32),
+    UpdateWorkspaces(Vec<Workspace>),
     UpdateWindows(Box<WindowEvent>),
+    FocusEmptyWorkspace,
+    UpdateOutputs,
 }
 
 impl Worker for AsyncHandler {
@@ -29,9 +31,13 @@ impl Worker for AsyncHandler {
     fn update(&mut self, msg: Input, sender: ComponentSender<Self>) {
         match msg {
             Input::Start => {
+                // the subscribed connection can't be used for queries
+                let mut query = Connection::new().unwrap();
+                send_workspaces(&mut query, &sender);
+
                 let connection = Connection::new().unwrap();
                 let mut events = connection
-                    .subscribe([EventType::Workspace, EventType::Window])
+                    .subscribe([EventType::Workspace, EventType::Window, EventType::Output])
                     .unwrap();
                 while let Some(x) = events.next() {
                     match x.unwrap() {
@@ -39,12 +45,20 @@ impl Worker for AsyncHandler {
                             sender.output(Output::UpdateWindows(x)).unwrap();
                         }
                         Event::Workspace(x) => {
-                            sender
-                                .output(Output::UpdateWorkspaces(match x.current {
-                                    Some(node) => node.num.unwrap(),
-                                    None => 1,
-                                }))
-                                .unwrap();
+                            // sway only reports window focus when a window gains it
+                            if matches!(x.change, WorkspaceChange::Focus) {
+                                if let Some(node) = &x.current {
+                                    if node.nodes.is_empty() && node.floating_nodes.is_empty() {
+                                        sender.output(Output::FocusEmptyWorkspace).unwrap();
+                                    }
+                                }
+                            }
+                            send_workspaces(&mut query, &sender);
+                        }
+                        Event::Output(_) => {
+                            sender.output(Output::UpdateOutputs).unwrap();
+                            // workspaces move when their output goes away
+                            send_workspaces(&mut query, &sender);
                         }
                         _ => {
                             continue;
@@ -55,3 +69,16 @@ impl Worker for AsyncHandler {
         }
     }
 }
+
+fn send_workspaces(connection: &mut Connection, sender: &ComponentSender<AsyncHandler>) {
+    match connection.get_workspaces() {
+        Ok(mut x) => {
+            // numbered workspaces first, then named ones in the order sway lists them
+            x.sort_by_key(|x| (x.num < 0, x.num));
+            sender.output(Output::UpdateWorkspaces(x)).unwrap();
+        }
+        Err(e) => {
+            log::error!("Failed to get workspaces: {e}");
+        }
+    }
+}
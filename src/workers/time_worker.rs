@@ -2,7 +2,11 @@ use std::time::Duration;
 
 use relm4::{gtk::glib::DateTime, ComponentSender, Worker};
 
-pub struct AsyncHandler;
+use crate::config::TimeWorkerConfig;
+
+pub struct AsyncHandler {
+    interval: Duration,
+}
 
 #[derive(Debug)]
 pub enum Input {
@@ -15,17 +19,19 @@ pub enum Output {
 }
 
 impl Worker for AsyncHandler {
-    type Init = ();
+    type Init = TimeWorkerConfig;
     type Input = Input;
     type Output = Output;
 
-    fn init(_init: Self::Init, sender: ComponentSender<Self>) -> Self {
+    fn init(config: Self::Init, sender: ComponentSender<Self>) -> Self {
         sender.input(Input::Start);
-        Self
+        Self {
+            interval: Duration::from_secs(config.interval),
+        }
     }
 
     fn update(&mut self, msg: Input, sender: ComponentSender<Self>) {
-        let interval = Duration::from_secs(2);
+        let interval = self.interval;
         match msg {
             Input::Start => loop {
                 let time = match DateTime::now_local() {